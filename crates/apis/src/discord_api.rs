@@ -1,25 +1,35 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
-use anyhow::{anyhow, Context};
-use chrono::{Duration, Utc};
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
 use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
 use regex::Regex;
+use reqwest::StatusCode;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
 use serenity::{
-    builder::CreateMessage,
-    http::Http,
+    builder::CreateEmbed,
+    http::HttpError,
     model::{
-        channel::{ChannelCategory, Message, MessageReference, MessageType},
-        id::{ChannelId, GuildId, RoleId, UserId},
+        channel::{AttachmentType, ChannelCategory, Message, MessageReference, MessageType},
+        id::{ChannelId, GuildId, MessageId, RoleId, UserId},
+        interactions::ButtonStyle,
         misc::Mention,
     },
     CacheAndHttp,
 };
+use strum::IntoEnumIterator;
+use strum_macros::{EnumIter, EnumString, ToString};
 use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex};
-use tracing::{debug, debug_span, error, info, instrument, Instrument};
+use tracing::{debug, debug_span, error, info, instrument, warn, Instrument};
 
 use utility::{
-    config::{Config, Reminder, ReminderLocation},
+    config::{
+        ArchivedMessage, ChatArchive, ChatLogExport, Config, EmbedTemplate, Reminder,
+        ReminderLocation, Subscription, SubscriptionEvent, TemplateKind, TweetKind,
+        TweetProperties, User,
+    },
     extensions::MessageExt,
     here, regex,
 };
@@ -27,17 +37,45 @@ use utility::{
 use crate::{
     birthday_reminder::Birthday,
     holo_api::{Livestream, StreamState, StreamUpdate},
-    twitter_api::{HoloTweet, HoloTweetReference, ScheduleUpdate},
+    translation_api::{TranslationApi, COMPARE_TWEET_BUTTON},
+    twitter_api::{
+        HoloFanart, HoloSpace, HoloTweet, HoloTweetReference, ScheduleUpdate, SpaceState,
+    },
 };
 
+/// Prefix of the custom IDs of the buttons that switch tweet posts between the original and translated text.
+pub const TWEET_VIEW_BUTTON: &str = "tweet_view";
+/// Name of the embed field holding the translation of a tweet.
+pub const TRANSLATION_FIELD: &str = "Machine Translation";
+
+/// Number of subscriber DMs queued at a time, to stay clear of Discord's spam detection.
+const SUBSCRIBER_DM_BATCH_SIZE: usize = 10;
+const SUBSCRIBER_DM_BATCH_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+/// Discord's upload limit for servers without boosts.
+const ATTACHMENT_LIMIT: usize = 8 * 1024 * 1024;
+
+mod chat_log;
+mod live_dashboard;
+mod outbox;
+mod schedule_digest;
+mod scheduled_events;
+mod tweet_translation;
+
+pub use chat_log::{render_html, render_markdown};
+pub use outbox::{Outbox, WebhookAuthor};
+
 pub struct DiscordApi;
 
 impl DiscordApi {
-    #[instrument(skip(ctx, config))]
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip(ctx, config, translator))]
     pub async fn start(
         ctx: Arc<CacheAndHttp>,
         config: Config,
+        translator: Arc<TranslationApi>,
         channel: mpsc::Receiver<DiscordMessageData>,
+        posting_sender: mpsc::Sender<DiscordMessageData>,
+        tweets: mpsc::Receiver<DiscordMessageData>,
         stream_notifier: broadcast::Receiver<StreamUpdate>,
         index_receiver: watch::Receiver<HashMap<u32, Livestream>>,
         guild_ready: oneshot::Receiver<()>,
@@ -45,19 +83,90 @@ impl DiscordApi {
     ) {
         let cache_copy = Arc::<serenity::CacheAndHttp>::clone(&ctx);
         let cache_copy2 = Arc::<serenity::CacheAndHttp>::clone(&ctx);
+        let cache_copy3 = Arc::<serenity::CacheAndHttp>::clone(&ctx);
+        let cache_copy4 = Arc::<serenity::CacheAndHttp>::clone(&ctx);
+        let cache_copy5 = Arc::<serenity::CacheAndHttp>::clone(&ctx);
 
         let config_copy = config.clone();
         let config_copy2 = config.clone();
+        let config_copy3 = config.clone();
+        let config_copy4 = config.clone();
+        let config_copy5 = config.clone();
+        let config_copy6 = config.clone();
+
+        let index_receiver_copy = index_receiver.clone();
+        let index_receiver_copy2 = index_receiver.clone();
+        let index_receiver_copy3 = index_receiver.clone();
 
         let mut exit_receiver_clone = exit_receiver.clone();
         let mut exit_receiver_clone2 = exit_receiver.clone();
+        let mut exit_receiver_clone3 = exit_receiver.clone();
+        let mut exit_receiver_clone4 = exit_receiver.clone();
+        let mut exit_receiver_clone5 = exit_receiver.clone();
+        let mut exit_receiver_clone6 = exit_receiver.clone();
+        let mut exit_receiver_clone7 = exit_receiver.clone();
 
         let (archive_tx, archive_rx) = mpsc::unbounded_channel();
+        let (digest_tx, digest_rx) = mpsc::unbounded_channel();
+        let (claimed_tx, claimed_rx) = watch::channel(HashMap::new());
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+
+        let outbox = match Outbox::new(&config, Arc::clone(&ctx.http)) {
+            Ok(outbox) => Arc::new(outbox),
+            Err(e) => {
+                error!("{:?}", e);
+                return;
+            }
+        };
+        let outbox_copy = Arc::clone(&outbox);
+        let outbox_copy2 = Arc::clone(&outbox);
+
+        tokio::spawn(
+            async move {
+                tokio::select! {
+                    _ = outbox_copy.delivery_thread() => {},
+                    e = exit_receiver_clone3.changed() => {
+                        if let Err(e) = e {
+                            error!("{:#}", e);
+                        }
+                    }
+                }
+
+                info!(task = "Discord outbox thread", "Shutting down.");
+            }
+            .instrument(debug_span!("Discord outbox thread")),
+        );
+
+        tokio::spawn(
+            async move {
+                tokio::select! {
+                    res = Self::tweet_translation_thread(
+                        cache_copy5,
+                        config_copy6,
+                        translator,
+                        tweets,
+                        posting_sender,
+                    ) => {
+                        if let Err(e) = res {
+                            error!("{:#}", e);
+                        }
+                    },
+                    e = exit_receiver_clone7.changed() => {
+                        if let Err(e) = e {
+                            error!("{:#}", e);
+                        }
+                    }
+                }
+
+                info!(task = "Discord tweet translation thread", "Shutting down.");
+            }
+            .instrument(debug_span!("Discord tweet translation thread")),
+        );
 
         tokio::spawn(
             async move {
                 tokio::select! {
-                    _ = Self::posting_thread(ctx, config, channel) => {},
+                    _ = Self::posting_thread(ctx, config, outbox, channel) => {},
                     e = exit_receiver.changed() => {
                         if let Err(e) = e {
                             error!("{:#}", e);
@@ -80,6 +189,9 @@ impl DiscordApi {
                         index_receiver,
                         guild_ready,
                         archive_tx,
+                        digest_tx,
+                        claimed_tx,
+                        events_tx,
                     ) => {
                         if let Err(e) = res {
                             error!("{:#}", e);
@@ -120,25 +232,371 @@ impl DiscordApi {
             }
             .instrument(debug_span!("Discord archiver thread")),
         );
+
+        tokio::spawn(
+            async move {
+                tokio::select! {
+                    res = Self::schedule_digest_thread(
+                        cache_copy3,
+                        config_copy3,
+                        outbox_copy2,
+                        index_receiver_copy,
+                        digest_rx,
+                    ) => {
+                        if let Err(e) = res {
+                            error!("{:#}", e);
+                        }
+                    },
+                    e = exit_receiver_clone4.changed() => {
+                        if let Err(e) = e {
+                            error!("{:#}", e);
+                        }
+                    }
+                }
+
+                info!(task = "Discord schedule digest thread", "Shutting down.");
+            }
+            .instrument(debug_span!("Discord schedule digest thread")),
+        );
+
+        if let Some(dashboard_channel) = config_copy4.live_dashboard_channel {
+            tokio::spawn(
+                async move {
+                    tokio::select! {
+                        res = Self::live_dashboard_thread(
+                            cache_copy4,
+                            ChannelId(dashboard_channel),
+                            index_receiver_copy2,
+                            claimed_rx,
+                        ) => {
+                            if let Err(e) = res {
+                                error!("{:#}", e);
+                            }
+                        },
+                        e = exit_receiver_clone5.changed() => {
+                            if let Err(e) = e {
+                                error!("{:#}", e);
+                            }
+                        }
+                    }
+
+                    info!(task = "Discord live dashboard thread", "Shutting down.");
+                }
+                .instrument(debug_span!("Discord live dashboard thread")),
+            );
+        }
+
+        if let Some(settings) = config_copy5.scheduled_events.clone() {
+            tokio::spawn(
+                async move {
+                    tokio::select! {
+                        res = Self::scheduled_events_thread(
+                            config_copy5,
+                            settings,
+                            index_receiver_copy3,
+                            events_rx,
+                        ) => {
+                            if let Err(e) = res {
+                                error!("{:#}", e);
+                            }
+                        },
+                        e = exit_receiver_clone6.changed() => {
+                            if let Err(e) = e {
+                                error!("{:#}", e);
+                            }
+                        }
+                    }
+
+                    info!(task = "Discord scheduled events thread", "Shutting down.");
+                }
+                .instrument(debug_span!("Discord scheduled events thread")),
+            );
+        }
     }
 
-    pub async fn send_message<'a, F: Sync + Send>(
-        http: &Arc<Http>,
-        channel: ChannelId,
-        f: F,
-    ) -> anyhow::Result<Message>
-    where
-        for<'b> F: FnOnce(&'b mut CreateMessage<'a>) -> &'b mut CreateMessage<'a>,
-    {
-        match channel.send_message(&http, f).await {
-            Ok(m) => Ok(m),
-            Err(e) => {
+    /// Sends the embed to the DMs of everyone subscribed to this kind of post from the talent.
+    /// The DMs are queued in batches in the background, so the channel posts aren't held up.
+    fn notify_subscribers(
+        ctx: &Arc<CacheAndHttp>,
+        outbox: &Arc<Outbox>,
+        handle: Option<&Connection>,
+        talent: &User,
+        event: SubscriptionEvent,
+        embed: &CreateEmbed,
+    ) {
+        let subscribers = match handle.map(|h| Subscription::subscribers(h, &talent.name, event)) {
+            Some(Ok(subscribers)) if !subscribers.is_empty() => subscribers,
+            Some(Err(e)) => {
                 error!("{:?}", e);
-                Err(anyhow!(e))
+                return;
+            }
+            _ => return,
+        };
+
+        debug!(
+            talent = %talent.display_name,
+            event = %event.to_string(),
+            count = subscribers.len(),
+            "Notifying subscribers."
+        );
+
+        let ctx = Arc::clone(ctx);
+        let outbox = Arc::clone(outbox);
+        let embed = embed.clone();
+
+        tokio::spawn(
+            async move {
+                for batch in subscribers.chunks(SUBSCRIBER_DM_BATCH_SIZE) {
+                    for subscriber in batch {
+                        let channel = match subscriber.create_dm_channel(&ctx).await {
+                            Ok(channel) => channel,
+                            Err(e) => {
+                                warn!(%subscriber, "Couldn't open DM: {:?}", e);
+                                continue;
+                            }
+                        };
+
+                        if let Err(e) = outbox
+                            .enqueue(channel.id, |m| m.set_embed(embed.clone()))
+                            .await
+                        {
+                            error!("{:?}", e);
+                        }
+                    }
+
+                    tokio::time::sleep(SUBSCRIBER_DM_BATCH_DELAY).await;
+                }
+            }
+            .instrument(debug_span!("Subscriber DMs")),
+        );
+    }
+
+    /// Loads the template of the guild the channel belongs to.
+    async fn load_template(
+        ctx: &Arc<CacheAndHttp>,
+        handle: Option<&Connection>,
+        channel: ChannelId,
+        kind: TemplateKind,
+    ) -> EmbedTemplate {
+        let guild = ctx.cache.guild_channel(channel).await.map(|c| c.guild_id);
+
+        match (handle, guild) {
+            (Some(handle), Some(guild)) => {
+                EmbedTemplate::load(handle, guild, kind).unwrap_or_else(|e| {
+                    error!("{:?}", e);
+                    EmbedTemplate::default_for(kind)
+                })
             }
+            _ => EmbedTemplate::default_for(kind),
         }
     }
 
+    /// Placeholder values shared by all templates.
+    fn template_values(
+        talent: &str,
+        mention: String,
+        time: &DateTime<Utc>,
+    ) -> HashMap<&'static str, String> {
+        let mut values = HashMap::new();
+
+        values.insert("talent", talent.to_owned());
+        values.insert("mention", mention);
+        values.insert("time", format!("<t:{}:F>", time.timestamp()));
+        values.insert("relative_time", format!("<t:{}:R>", time.timestamp()));
+
+        values
+    }
+
+    fn save_translated_tweet(
+        handle: &Connection,
+        message: MessageId,
+        original: &str,
+        translation: &str,
+    ) -> anyhow::Result<()> {
+        handle
+            .execute(
+                "INSERT OR REPLACE INTO TranslatedTweets (message_id, original, translation, posted_at) VALUES (?, ?, ?, ?)",
+                params![message.as_u64(), original, translation, Utc::now()],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Returns the original and translated text of a tweet post.
+    pub fn load_translated_tweet(
+        handle: &Connection,
+        message: MessageId,
+    ) -> anyhow::Result<Option<(String, String)>> {
+        handle
+            .query_row(
+                "SELECT original, translation FROM TranslatedTweets WHERE message_id = ?",
+                [message.as_u64()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .context(here!())
+    }
+
+    /// Adds a delivered tweet post to the cache used to find the posts of replied to tweets.
+    async fn track_delivery(
+        ctx: &Arc<CacheAndHttp>,
+        tweet_messages: &mut HashMap<u64, CachedTweet>,
+        delivery: Delivery,
+        message: Message,
+    ) {
+        if let Delivery::Tweet {
+            tweet_id,
+            author,
+            text,
+            ..
+        } = delivery
+        {
+            let link = message.link_ensured(ctx).await;
+
+            tweet_messages
+                .entry(tweet_id)
+                .or_insert_with(|| CachedTweet {
+                    author,
+                    text,
+                    messages: Vec::new(),
+                })
+                .messages
+                .push(PostedTweet {
+                    reference: MessageReference::from((message.channel_id, message.id)),
+                    link,
+                });
+        }
+    }
+
+    fn save_live_notification(
+        handle: &Connection,
+        video_id: &str,
+        message: &Message,
+    ) -> anyhow::Result<()> {
+        handle
+            .execute(
+                "INSERT OR REPLACE INTO LiveNotifications (video_id, channel_id, message_id, posted_at) VALUES (?, ?, ?, ?)",
+                params![video_id, message.channel_id.as_u64(), message.id.as_u64(), Utc::now()],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Turns the live notification of an ended stream into a summary of the stream, linking
+    /// to the VOD and the archived chat log.
+    #[instrument(skip(ctx, config, stream), fields(stream = %stream.title))]
+    async fn finish_live_notification(
+        ctx: &Arc<CacheAndHttp>,
+        config: &Config,
+        stream: &Livestream,
+        log_link: Option<String>,
+    ) -> anyhow::Result<()> {
+        let handle = config.get_database_handle()?;
+
+        let notification = handle
+            .query_row(
+                "SELECT channel_id, message_id FROM LiveNotifications WHERE video_id = ?",
+                [&stream.url],
+                |row| Ok((ChannelId(row.get(0)?), MessageId(row.get(1)?))),
+            )
+            .optional()
+            .context(here!())?;
+
+        let (channel, message_id) = match notification {
+            Some(n) => n,
+            None => return Ok(()),
+        };
+
+        let forget = || {
+            handle
+                .execute(
+                    "DELETE FROM LiveNotifications WHERE video_id = ?",
+                    [&stream.url],
+                )
+                .context(here!())
+        };
+
+        let message = match channel.message(&ctx.http, message_id).await {
+            Ok(m) => m,
+            // The notification was deleted, so there's nothing left to update.
+            Err(e) if is_not_found(&e) => {
+                forget()?;
+                return Ok(());
+            }
+            Err(e) => return Err(e).context(here!()),
+        };
+
+        let mut embed = match message.embeds.into_iter().next() {
+            Some(e) => CreateEmbed::from(e),
+            None => return Ok(()),
+        };
+
+        let url = format!("https://youtube.com/watch?v={}", stream.url);
+        let ended_at = stream
+            .duration
+            .map_or_else(Utc::now, |d| stream.start_at + Duration::seconds(d.into()));
+        let duration = chrono_humanize::HumanTime::from(ended_at - stream.start_at).to_text_en(
+            chrono_humanize::Accuracy::Precise,
+            chrono_humanize::Tense::Present,
+        );
+
+        let mut values = Self::template_values(
+            &stream.streamer.display_name,
+            String::new(),
+            &stream.start_at,
+        );
+        values.insert("title", stream.title.clone());
+        values.insert("url", url.clone());
+
+        Self::load_template(ctx, Some(&handle), channel, TemplateKind::LiveEnded)
+            .await
+            .render(&values)
+            .apply(&mut embed)
+            .field("Duration", duration, true)
+            .field("Ended", format!("<t:{}:f>", ended_at.timestamp()), true)
+            .field("VOD", format!("[Watch on YouTube]({})", url), true);
+
+        if let Some(link) = log_link {
+            embed.field(
+                "Chat log",
+                format!("[Read the archived chat]({})", link),
+                true,
+            );
+        }
+
+        let edited = channel
+            .edit_message(&ctx.http, message_id, |m| {
+                m.embed(|e| {
+                    *e = embed;
+                    e
+                })
+            })
+            .await;
+
+        match edited {
+            Ok(_) => (),
+            Err(e) if is_not_found(&e) => (),
+            Err(e) => return Err(e).context(here!()),
+        }
+
+        forget()?;
+
+        Ok(())
+    }
+
+    /// Returns the channels the tweet is routed to.
+    fn tweet_destinations(config: &Config, tweet: &HoloTweet) -> Vec<ChannelId> {
+        config.route_tweet(&TweetProperties {
+            user: &tweet.user,
+            kind: tweet.kind,
+            lang: tweet.lang.as_deref(),
+            text: &tweet.text,
+            has_media: !tweet.media.is_empty(),
+        })
+    }
+
     #[instrument(skip(ctx))]
     async fn search_for_tweet(
         ctx: &Arc<CacheAndHttp>,
@@ -180,138 +638,490 @@ impl DiscordApi {
     }
 
     #[allow(clippy::too_many_lines)]
-    #[instrument(skip(ctx, config))]
+    #[instrument(skip(ctx, config, outbox))]
     async fn posting_thread(
         ctx: Arc<CacheAndHttp>,
         config: Config,
+        outbox: Arc<Outbox>,
         mut channel: mpsc::Receiver<DiscordMessageData>,
     ) {
-        let mut tweet_messages: HashMap<u64, MessageReference> = HashMap::new();
+        let mut tweet_messages: HashMap<u64, CachedTweet> = HashMap::new();
+
+        let db_handle = match config.get_database_handle() {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                error!("Translated tweets won't be saved: {:?}", e);
+                None
+            }
+        };
+
+        // Posts are tracked once they're delivered, so the thread never waits on Discord.
+        let mut delivered = outbox.deliveries();
 
         loop {
-            if let Some(msg) = channel
-                .recv()
-                .instrument(debug_span!("Waiting for Discord message request."))
-                .await
-            {
+            let msg = tokio::select! {
+                msg = channel
+                    .recv()
+                    .instrument(debug_span!("Waiting for Discord message request.")) => msg,
+
+                res = delivered.recv() => {
+                    match res {
+                        Ok((delivery, message)) => {
+                            Self::track_delivery(&ctx, &mut tweet_messages, delivery, message)
+                                .await;
+                        }
+                        Err(e) => warn!("Missed delivered posts: {:?}", e),
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(msg) = msg {
                 match msg {
                     DiscordMessageData::Tweet(tweet) => {
                         let user = &tweet.user;
                         let role: RoleId = user.discord_role.into();
 
-                        let twitter_channel = user.get_twitter_channel(&config);
-                        let mut cross_channel_reply = false;
-                        let mut message_ref: Option<MessageReference> = None;
-
-                        // Try to reply to an existing Discord twitter message.
-                        if let Some(tweet_ref) = &tweet.replied_to {
-                            // Check if message exists in our cache.
-                            if let Some(msg_ref) = tweet_messages.get(&tweet_ref.tweet) {
-                                // Only allow if in the same channel until Discord allows for cross-channel replies.
-                                if msg_ref.channel_id == twitter_channel {
-                                    message_ref = Some(msg_ref.clone());
-                                }
-                            }
-                            // Else, search through the latest 100 tweets in the channel.
-                            else if let Some(tweet_user) =
-                                config.users.iter().find(|u| u.twitter_id == tweet_ref.user)
-                            {
-                                let tweet_channel = tweet_user.get_twitter_channel(&config);
+                        let destinations = Self::tweet_destinations(&config, &tweet);
 
-                                // Only allow if in the same channel until Discord allows for cross-channel replies.
-                                cross_channel_reply = tweet_channel == twitter_channel;
-                                message_ref =
-                                    Self::search_for_tweet(&ctx, tweet_ref, tweet_channel).await;
-                            }
+                        // Subscribers get every tweet of the talent, regardless of the routes.
+                        let mut embed = CreateEmbed::default();
+                        embed
+                            .description(&tweet.text)
+                            .url(&tweet.link)
+                            .timestamp(&tweet.timestamp)
+                            .colour(user.colour)
+                            .author(|a| {
+                                a.name(&user.display_name)
+                                    .url(&tweet.link)
+                                    .icon_url(&user.icon)
+                            });
+
+                        if let [image, ..] = &tweet.media[..] {
+                            embed.image(image);
                         }
 
-                        let message = Self::send_message(&ctx.http, twitter_channel, |m| {
-                            m.allowed_mentions(|am| am.empty_parse().roles(vec![role]))
-                                .embed(|e| {
-                                    e.description(&tweet.text)
-                                        .timestamp(&tweet.timestamp)
-                                        .colour(user.colour)
-                                        .author(|a| {
-                                            a.name(&user.display_name);
-                                            a.url(&tweet.link);
-                                            a.icon_url(&user.icon);
-
-                                            a
-                                        });
-
-                                    match &tweet.media[..] {
-                                        [] => (),
-                                        [a, ..] => {
-                                            e.image(a);
-                                        }
-                                    };
+                        Self::notify_subscribers(
+                            &ctx,
+                            &outbox,
+                            db_handle.as_ref(),
+                            user,
+                            SubscriptionEvent::Tweet,
+                            &embed,
+                        );
+
+                        if destinations.is_empty() {
+                            debug!("No route matched tweet from {}.", user.display_name);
+                            continue;
+                        }
 
-                                    if let Some(translation) = &tweet.translation {
-                                        e.field("Machine Translation", translation, false);
+                        // If the tweet being replied to isn't cached, search for it once, in the
+                        // first channel that can reply to it.
+                        let searched_reply = match &tweet.replied_to {
+                            Some(tweet_ref)
+                                if !tweet_messages.contains_key(&tweet_ref.tweet)
+                                    && config
+                                        .users
+                                        .iter()
+                                        .any(|u| u.twitter_id == tweet_ref.user) =>
+                            {
+                                match destinations
+                                    .iter()
+                                    .find(|c| !config.webhook_channels.contains(c.as_u64()))
+                                {
+                                    Some(channel) => {
+                                        Self::search_for_tweet(&ctx, tweet_ref, *channel).await
                                     }
+                                    None => None,
+                                }
+                            }
+                            _ => None,
+                        };
+
+                        for twitter_channel in destinations {
+                            // Translated ahead of time by the tweet translation thread.
+                            let translation = match &tweet.lang {
+                                Some(lang) => {
+                                    let guild = ctx
+                                        .cache
+                                        .guild_channel(twitter_channel)
+                                        .await
+                                        .map(|c| c.guild_id);
+                                    let policy =
+                                        config.translation.policy_for(twitter_channel, guild);
+
+                                    if policy.should_translate(lang) {
+                                        tweet.translations.get(&policy.target).cloned()
+                                    } else {
+                                        None
+                                    }
+                                }
+                                None => None,
+                            };
 
-                                    e
-                                });
+                            let webhook_author =
+                                if config.webhook_channels.contains(twitter_channel.as_u64()) {
+                                    Some(WebhookAuthor {
+                                        name: user.display_name.clone(),
+                                        avatar: Some(user.icon.to_string()),
+                                    })
+                                } else {
+                                    None
+                                };
+
+                            let mut message_ref: Option<MessageReference> = None;
+                            let mut cross_channel_reply: Option<CrossChannelReply> = None;
+
+                            // Try to reply to an existing Discord twitter message.
+                            if let Some(tweet_ref) = &tweet.replied_to {
+                                // Check if message exists in our cache.
+                                if let Some(cached) = tweet_messages.get(&tweet_ref.tweet) {
+                                    // Webhooks can't reply, so they always link to the original message.
+                                    if webhook_author.is_none() {
+                                        message_ref = cached
+                                            .messages
+                                            .iter()
+                                            .find(|p| p.reference.channel_id == twitter_channel)
+                                            .map(|p| p.reference.clone());
+                                    }
 
-                            if let Some(msg_ref) = message_ref {
-                                if !cross_channel_reply {
-                                    m.reference_message(msg_ref);
+                                    // Link to the original message until Discord allows for cross-channel replies.
+                                    if message_ref.is_none() {
+                                        cross_channel_reply =
+                                            cached.messages.first().map(|p| CrossChannelReply {
+                                                author: cached.author.clone(),
+                                                link: p.link.clone(),
+                                                text: cached.text.clone(),
+                                            });
+                                    }
+                                }
+                                // Else, use the message found in the latest 100 in the channel.
+                                else if webhook_author.is_none() {
+                                    message_ref = searched_reply
+                                        .clone()
+                                        .filter(|r| r.channel_id == twitter_channel);
                                 }
                             }
 
-                            m
-                        })
-                        .await
-                        .context(here!());
-
-                        match message {
-                            Ok(m) => {
-                                tweet_messages.insert(
-                                    tweet.id,
-                                    MessageReference::from((twitter_channel, m.id)),
-                                );
-                            }
-                            Err(e) => {
+                            let mut values = Self::template_values(
+                                &user.display_name,
+                                Mention::from(role).to_string(),
+                                &tweet.timestamp,
+                            );
+                            values.insert("text", tweet.text.clone());
+                            values.insert("url", tweet.link.clone());
+
+                            let template = Self::load_template(
+                                &ctx,
+                                db_handle.as_ref(),
+                                twitter_channel,
+                                TemplateKind::Tweet,
+                            )
+                            .await
+                            .render(&values);
+
+                            let delivery = Delivery::Tweet {
+                                tweet_id: tweet.id,
+                                author: user.display_name.clone(),
+                                text: tweet.text.clone(),
+                                translation: translation.clone(),
+                            };
+
+                            let queued = outbox
+                                .enqueue_as(
+                                    twitter_channel,
+                                    webhook_author.as_ref(),
+                                    Some(delivery),
+                                    |m| {
+                                        if let Some(content) = &template.content {
+                                            m.content(content);
+                                        }
+
+                                        m.allowed_mentions(|am| am.empty_parse().roles(vec![role]))
+                                            .embed(|e| {
+                                                template
+                                                    .apply(e)
+                                                    .timestamp(&tweet.timestamp)
+                                                    .colour(user.colour)
+                                                    .author(|a| {
+                                                        a.name(&user.display_name);
+                                                        a.url(&tweet.link);
+                                                        a.icon_url(&user.icon);
+
+                                                        a
+                                                    });
+
+                                                match &tweet.media[..] {
+                                                    [] => (),
+                                                    [a, ..] => {
+                                                        e.image(a);
+                                                    }
+                                                };
+
+                                                if let Some(reply) = &cross_channel_reply {
+                                                    let field_name = match tweet.kind {
+                                                        TweetKind::Quote => {
+                                                            format!("Quoting {}", reply.author)
+                                                        }
+                                                        TweetKind::Reply | TweetKind::Original => {
+                                                            format!("Replying to {}", reply.author)
+                                                        }
+                                                    };
+
+                                                    e.field(
+                                                        field_name,
+                                                        reply.format(
+                                                            config.quote_cross_channel_replies,
+                                                        ),
+                                                        false,
+                                                    );
+                                                }
+
+                                                if let Some(translation) = &translation {
+                                                    e.field(TRANSLATION_FIELD, translation, false);
+                                                }
+
+                                                e
+                                            });
+
+                                        if let Some(msg_ref) = message_ref {
+                                            m.reference_message(msg_ref);
+                                        }
+
+                                        if translation.is_some() {
+                                            m.components(|c| {
+                                                c.create_action_row(|r| {
+                                                    for view in TweetView::iter() {
+                                                        r.create_button(|b| {
+                                                            b.style(ButtonStyle::Secondary)
+                                                                .label(view.label())
+                                                                .custom_id(format!(
+                                                                    "{}:{}",
+                                                                    TWEET_VIEW_BUTTON,
+                                                                    view.to_string()
+                                                                ))
+                                                        });
+                                                    }
+
+                                                    r.create_button(|b| {
+                                                        b.style(ButtonStyle::Secondary)
+                                                            .label("Compare translations")
+                                                            .custom_id(format!(
+                                                                "{}:{}",
+                                                                COMPARE_TWEET_BUTTON, tweet.id
+                                                            ))
+                                                    })
+                                                })
+                                            });
+                                        }
+
+                                        m
+                                    },
+                                )
+                                .await
+                                .context(here!());
+
+                            if let Err(e) = queued {
                                 error!("{:?}", e);
-                                continue;
                             }
                         }
                     }
-                    DiscordMessageData::ScheduledLive(live) => {
-                        if let Some(user) = config.users.iter().find(|u| **u == live.streamer) {
-                            let livestream_channel = ChannelId(config.live_notif_channel);
-                            let role: RoleId = user.discord_role.into();
+                    DiscordMessageData::Fanart(fanart) => {
+                        let fanart_channel = match &config.fanart {
+                            Some(c) => ChannelId(c.channel),
+                            None => continue,
+                        };
+
+                        let user = &fanart.user;
+
+                        let message =
+                            outbox
+                                .enqueue(fanart_channel, |m| {
+                                    // Embed images can't be spoilered, so sensitive art is posted as spoilered links instead.
+                                    if fanart.spoiler {
+                                        m.content(
+                                            fanart
+                                                .media
+                                                .iter()
+                                                .map(|url| format!("||{}||", url))
+                                                .join("\n"),
+                                        );
+                                    }
 
-                            let message = Self::send_message(&ctx.http, livestream_channel, |m| {
-                                m.content(Mention::from(role))
-                                    .allowed_mentions(|am| am.empty_parse().roles(vec![role]))
-                                    .embed(|e| {
-                                        e.title(format!("{} just went live!", user.display_name))
-                                            .description(live.title)
-                                            .url(format!(
-                                                "https://youtube.com/watch?v={}",
-                                                live.url
-                                            ))
-                                            .timestamp(&live.start_at)
+                                    m.allowed_mentions(|am| am.empty_parse()).embed(|e| {
+                                        e.title(format!("Fanart of {}", user.display_name))
+                                            .description(&fanart.text)
+                                            .url(&fanart.link)
+                                            .timestamp(&fanart.timestamp)
                                             .colour(user.colour)
-                                            .image(format!(
-                                                "https://i3.ytimg.com/vi/{}/maxresdefault.jpg",
-                                                live.url
-                                            ))
                                             .author(|a| {
-                                                a.name(&user.display_name)
-                                                    .url(format!(
-                                                        "https://www.youtube.com/channel/{}",
-                                                        user.channel
-                                                    ))
-                                                    .icon_url(&user.icon)
+                                                a.name(format!(
+                                                    "{} (@{})",
+                                                    fanart.artist_name, fanart.artist_handle
+                                                ))
+                                                .url(format!(
+                                                    "https://twitter.com/{}",
+                                                    fanart.artist_handle
+                                                ));
+
+                                                if let Some(icon) = &fanart.artist_icon {
+                                                    a.icon_url(icon);
+                                                }
+
+                                                a
                                             })
+                                            .footer(|f| {
+                                                f.text(format!("Art by @{}", fanart.artist_handle))
+                                            });
+
+                                        if !fanart.spoiler {
+                                            if let [image, ..] = &fanart.media[..] {
+                                                e.image(image);
+                                            }
+                                        }
+
+                                        e
+                                    })
+                                })
+                                .await
+                                .context(here!());
+
+                        if let Err(e) = message {
+                            error!("{:?}", e);
+                            continue;
+                        }
+                    }
+                    DiscordMessageData::Space(space) => {
+                        let user = &space.user;
+                        let livestream_channel = ChannelId(config.live_notif_channel);
+                        let role: RoleId = user.discord_role.into();
+
+                        let message = outbox
+                            .enqueue(livestream_channel, |m| {
+                                // Only ping once, when the Space goes live.
+                                if space.state == SpaceState::Live {
+                                    m.content(Mention::from(role));
+                                }
+
+                                m.allowed_mentions(|am| am.empty_parse().roles(vec![role]))
+                                    .embed(|e| {
+                                        match space.state {
+                                            SpaceState::Scheduled => {
+                                                e.title(format!(
+                                                    "{} scheduled a Twitter Space!",
+                                                    user.display_name
+                                                ));
+
+                                                if let Some(start) = &space.scheduled_start {
+                                                    e.timestamp(start).field(
+                                                        "Starts",
+                                                        format!("<t:{}:R>", start.timestamp()),
+                                                        false,
+                                                    );
+                                                }
+                                            }
+                                            SpaceState::Live => {
+                                                e.title(format!(
+                                                    "{} is live on Twitter Spaces!",
+                                                    user.display_name
+                                                ))
+                                                .timestamp(
+                                                    &space.started_at.unwrap_or_else(Utc::now),
+                                                );
+                                            }
+                                        }
+
+                                        if let Some(title) = &space.title {
+                                            e.description(title);
+                                        }
+
+                                        e.url(space.link()).colour(user.colour).author(|a| {
+                                            a.name(&user.display_name)
+                                                .url(format!(
+                                                    "https://twitter.com/{}",
+                                                    user.twitter_handle
+                                                ))
+                                                .icon_url(&user.icon)
+                                        })
                                     })
                             })
                             .await
                             .context(here!());
 
-                            if let Err(e) = message {
+                        if let Err(e) = message {
+                            error!("{:?}", e);
+                            continue;
+                        }
+                    }
+                    DiscordMessageData::ScheduledLive(live) => {
+                        if let Some(user) = config.users.iter().find(|u| **u == live.streamer) {
+                            let livestream_channel = ChannelId(config.live_notif_channel);
+                            let role: RoleId = user.discord_role.into();
+
+                            let url = format!("https://youtube.com/watch?v={}", live.url);
+
+                            let mut values = Self::template_values(
+                                &user.display_name,
+                                Mention::from(role).to_string(),
+                                &live.start_at,
+                            );
+                            values.insert("title", live.title.clone());
+                            values.insert("url", url.clone());
+
+                            let template = Self::load_template(
+                                &ctx,
+                                db_handle.as_ref(),
+                                livestream_channel,
+                                TemplateKind::Live,
+                            )
+                            .await
+                            .render(&values);
+
+                            let mut embed = CreateEmbed::default();
+                            template
+                                .apply(&mut embed)
+                                .url(url)
+                                .timestamp(&live.start_at)
+                                .colour(user.colour)
+                                .image(format!(
+                                    "https://i3.ytimg.com/vi/{}/maxresdefault.jpg",
+                                    live.url
+                                ))
+                                .author(|a| {
+                                    a.name(&user.display_name)
+                                        .url(format!(
+                                            "https://www.youtube.com/channel/{}",
+                                            user.channel
+                                        ))
+                                        .icon_url(&user.icon)
+                                });
+
+                            Self::notify_subscribers(
+                                &ctx,
+                                &outbox,
+                                db_handle.as_ref(),
+                                user,
+                                SubscriptionEvent::Live,
+                                &embed,
+                            );
+
+                            let delivery = Delivery::Live {
+                                video_id: live.url.clone(),
+                            };
+
+                            let queued = outbox
+                                .enqueue_as(livestream_channel, None, Some(delivery), |m| {
+                                    if let Some(content) = &template.content {
+                                        m.content(content);
+                                    }
+
+                                    m.allowed_mentions(|am| am.empty_parse().roles(vec![role]))
+                                        .set_embed(embed)
+                                })
+                                .await
+                                .context(here!());
+
+                            if let Err(e) = queued {
                                 error!("{:?}", e);
                                 continue;
                             }
@@ -326,34 +1136,62 @@ impl DiscordApi {
                             let schedule_channel = ChannelId(config.schedule_channel);
                             let role: RoleId = user.discord_role.into();
 
-                            let message = Self::send_message(&ctx.http, schedule_channel, |m| {
-                                m.content(Mention::from(role))
-                                    .allowed_mentions(|am| {
+                            let mut values = Self::template_values(
+                                &user.display_name,
+                                Mention::from(role).to_string(),
+                                &update.timestamp,
+                            );
+                            values.insert("text", update.tweet_text.clone());
+                            values.insert("url", update.tweet_link.clone());
+
+                            let template = Self::load_template(
+                                &ctx,
+                                db_handle.as_ref(),
+                                schedule_channel,
+                                TemplateKind::ScheduleUpdate,
+                            )
+                            .await
+                            .render(&values);
+
+                            let mut embed = CreateEmbed::default();
+                            template
+                                .apply(&mut embed)
+                                .url(update.tweet_link)
+                                .timestamp(&update.timestamp)
+                                .colour(user.colour)
+                                .image(update.schedule_image)
+                                .author(|a| {
+                                    a.name(&user.display_name)
+                                        .url(format!(
+                                            "https://www.youtube.com/channel/{}",
+                                            user.channel
+                                        ))
+                                        .icon_url(&user.icon)
+                                });
+
+                            Self::notify_subscribers(
+                                &ctx,
+                                &outbox,
+                                db_handle.as_ref(),
+                                user,
+                                SubscriptionEvent::Schedule,
+                                &embed,
+                            );
+
+                            let message = outbox
+                                .enqueue(schedule_channel, |m| {
+                                    if let Some(content) = &template.content {
+                                        m.content(content);
+                                    }
+
+                                    m.allowed_mentions(|am| {
                                         am.empty_parse();
                                         am.roles(vec![role])
                                     })
-                                    .embed(|e| {
-                                        e.title(format!(
-                                            "{} just released a schedule update!",
-                                            user.display_name
-                                        ))
-                                        .description(update.tweet_text)
-                                        .url(update.tweet_link)
-                                        .timestamp(&update.timestamp)
-                                        .colour(user.colour)
-                                        .image(update.schedule_image)
-                                        .author(|a| {
-                                            a.name(&user.display_name)
-                                                .url(format!(
-                                                    "https://www.youtube.com/channel/{}",
-                                                    user.channel
-                                                ))
-                                                .icon_url(&user.icon)
-                                        })
-                                    })
-                            })
-                            .await
-                            .context(here!());
+                                    .set_embed(embed)
+                                })
+                                .await
+                                .context(here!());
 
                             if let Err(e) = message {
                                 error!("{:?}", e);
@@ -370,28 +1208,55 @@ impl DiscordApi {
                             let birthday_channel = ChannelId(config.birthday_notif_channel);
                             let role: RoleId = user.discord_role.into();
 
-                            let message = Self::send_message(&ctx.http, birthday_channel, |m| {
-                                m.content(Mention::from(role))
-                                    .allowed_mentions(|am| am.empty_parse().roles(vec![role]))
-                                    .embed(|e| {
-                                        e.title(format!(
-                                            "It is {}'s birthday today!!!",
-                                            user.display_name
-                                        ))
-                                        .timestamp(&birthday.birthday)
-                                        .colour(user.colour)
-                                        .author(|a| {
-                                            a.name(&user.display_name)
-                                                .url(format!(
-                                                    "https://www.youtube.com/channel/{}",
-                                                    user.channel
-                                                ))
-                                                .icon_url(&user.icon)
-                                        })
-                                    })
-                            })
+                            let values = Self::template_values(
+                                &user.display_name,
+                                Mention::from(role).to_string(),
+                                &birthday.birthday,
+                            );
+
+                            let template = Self::load_template(
+                                &ctx,
+                                db_handle.as_ref(),
+                                birthday_channel,
+                                TemplateKind::Birthday,
+                            )
                             .await
-                            .context(here!());
+                            .render(&values);
+
+                            let mut embed = CreateEmbed::default();
+                            template
+                                .apply(&mut embed)
+                                .timestamp(&birthday.birthday)
+                                .colour(user.colour)
+                                .author(|a| {
+                                    a.name(&user.display_name)
+                                        .url(format!(
+                                            "https://www.youtube.com/channel/{}",
+                                            user.channel
+                                        ))
+                                        .icon_url(&user.icon)
+                                });
+
+                            Self::notify_subscribers(
+                                &ctx,
+                                &outbox,
+                                db_handle.as_ref(),
+                                user,
+                                SubscriptionEvent::Birthday,
+                                &embed,
+                            );
+
+                            let message = outbox
+                                .enqueue(birthday_channel, |m| {
+                                    if let Some(content) = &template.content {
+                                        m.content(content);
+                                    }
+
+                                    m.allowed_mentions(|am| am.empty_parse().roles(vec![role]))
+                                        .set_embed(embed)
+                                })
+                                .await
+                                .context(here!());
 
                             if let Err(e) = message {
                                 error!("{:?}", e);
@@ -424,24 +1289,37 @@ impl DiscordApi {
                         }
 
                         for (channel, (users, public)) in channel_map {
-                            let result = channel
-                                .send_message(&ctx.http, |m| {
-                                    if public {
-                                        m.content(
-                                            users
-                                                .into_iter()
-                                                .fold(String::new(), |acc, u| {
-                                                    acc + &format!("{} ", Mention::from(u))
-                                                })
-                                                .trim(),
-                                        );
+                            let mention = if public {
+                                users
+                                    .into_iter()
+                                    .fold(String::new(), |acc, u| {
+                                        acc + &format!("{} ", Mention::from(u))
+                                    })
+                                    .trim()
+                                    .to_owned()
+                            } else {
+                                String::new()
+                            };
+
+                            let mut values = Self::template_values("", mention, &reminder.time);
+                            values.insert("text", reminder.message.clone());
+
+                            let template = Self::load_template(
+                                &ctx,
+                                db_handle.as_ref(),
+                                channel,
+                                TemplateKind::Reminder,
+                            )
+                            .await
+                            .render(&values);
+
+                            let result = outbox
+                                .enqueue(channel, |m| {
+                                    if let Some(content) = &template.content {
+                                        m.content(content);
                                     }
 
-                                    m.embed(|e| {
-                                        e.title("Reminder!")
-                                            .description(&reminder.message)
-                                            .timestamp(&reminder.time)
-                                    })
+                                    m.embed(|e| template.apply(e).timestamp(&reminder.time))
                                 })
                                 .await;
 
@@ -456,14 +1334,17 @@ impl DiscordApi {
         }
     }
 
-    #[allow(clippy::no_effect)]
+    #[allow(clippy::no_effect, clippy::too_many_arguments)]
     #[instrument(skip(
         ctx,
         config,
         stream_notifier,
         index_receiver,
         guild_ready,
-        stream_archiver
+        stream_archiver,
+        digest_refresher,
+        claimed_notifier,
+        event_updates
     ))]
     async fn stream_update_thread(
         ctx: Arc<CacheAndHttp>,
@@ -472,6 +1353,9 @@ impl DiscordApi {
         mut index_receiver: watch::Receiver<HashMap<u32, Livestream>>,
         guild_ready: oneshot::Receiver<()>,
         stream_archiver: mpsc::UnboundedSender<(ChannelId, Option<Livestream>)>,
+        digest_refresher: mpsc::UnboundedSender<StreamUpdate>,
+        claimed_notifier: watch::Sender<HashMap<u32, ChannelId>>,
+        event_updates: mpsc::UnboundedSender<StreamUpdate>,
     ) -> anyhow::Result<()> {
         let _ = guild_ready.await.context(here!())?;
 
@@ -515,6 +1399,9 @@ impl DiscordApi {
             claimed_channels.insert(stream.id, claimed_channel);
         }
 
+        // The live dashboard is optional, so there might not be anyone listening.
+        let _ = claimed_notifier.send(claimed_channels.clone());
+
         loop {
             let update = match stream_notifier.recv().await {
                 Ok(u) => u,
@@ -524,6 +1411,9 @@ impl DiscordApi {
                 }
             };
 
+            // Scheduled events are optional, so there might not be anyone listening.
+            let _ = event_updates.send(update.clone());
+
             match update {
                 StreamUpdate::Started(stream) => {
                     info!(loc = here!(), stream = %stream.title, "Stream started!");
@@ -534,18 +1424,33 @@ impl DiscordApi {
                     let claim = Self::claim_channel(&ctx, &active_category, &stream).await?;
 
                     claimed_channels.insert(stream.id, claim);
+                    let _ = claimed_notifier.send(claimed_channels.clone());
                 }
                 StreamUpdate::Ended(stream) => {
                     info!(loc = here!(), stream = %stream.title, "Stream ended!");
 
                     let claimed_channel = match claimed_channels.remove(&stream.id) {
                         Some(s) => s,
-                        None => continue,
+                        None => {
+                            if let Err(e) =
+                                Self::finish_live_notification(&ctx, &config, &stream, None).await
+                            {
+                                error!("{:?}", e);
+                            }
+                            continue;
+                        }
                     };
 
+                    let _ = claimed_notifier.send(claimed_channels.clone());
                     stream_archiver.send((claimed_channel, Some(stream)))?;
                 }
-                _ => (),
+                // Schedule digests are optional, so there might not be anyone listening.
+                StreamUpdate::Scheduled(stream) => {
+                    let _ = digest_refresher.send(StreamUpdate::Scheduled(stream));
+                }
+                StreamUpdate::Cancelled(stream) => {
+                    let _ = digest_refresher.send(StreamUpdate::Cancelled(stream));
+                }
             }
         }
     }
@@ -594,10 +1499,32 @@ impl DiscordApi {
         while let Some((channel, stream)) = archive_notifier.recv().await {
             let log_clone = Arc::clone(&log_ch);
             let ctx_clone = Arc::clone(&ctx);
+            let config_clone = config.clone();
 
             let _ = tokio::spawn(async move {
-                if let Err(e) = Self::archive_channel(ctx_clone, channel, stream, log_clone).await {
-                    error!("{:?}", e);
+                let log_link = match Self::archive_channel(
+                    &ctx_clone,
+                    channel,
+                    stream.as_ref(),
+                    log_clone,
+                    &config_clone,
+                )
+                .await
+                {
+                    Ok(link) => link,
+                    Err(e) => {
+                        error!("{:?}", e);
+                        None
+                    }
+                };
+
+                if let Some(stream) = stream {
+                    if let Err(e) =
+                        Self::finish_live_notification(&ctx_clone, &config_clone, &stream, log_link)
+                            .await
+                    {
+                        error!("{:?}", e);
+                    }
                 }
             });
         }
@@ -605,48 +1532,169 @@ impl DiscordApi {
         Ok(())
     }
 
+    /// Moves the chat of a stream into the log channel and returns a link to the log, if
+    /// anything was worth archiving.
     async fn archive_channel(
-        ctx: Arc<CacheAndHttp>,
+        ctx: &Arc<CacheAndHttp>,
         channel: ChannelId,
-        stream: Option<Livestream>,
+        stream: Option<&Livestream>,
         log_channel: Arc<Mutex<ChannelId>>,
-    ) -> anyhow::Result<()> {
+        config: &Config,
+    ) -> anyhow::Result<Option<String>> {
         let http = &ctx.http;
         let cache = &ctx.cache;
 
         let message_stream = channel.messages_iter(&http);
-        let stream_start = match stream.as_ref() {
+        let stream_start = match stream {
             Some(s) => s.start_at,
             None => channel.created_at(),
         };
-        let stream_id = stream.as_ref().map(|s| &s.url);
+        let stream_id = stream.map(|s| &s.url);
 
-        let messages = message_stream
+        let mut messages = message_stream
             .try_filter_map(|msg| async move {
                 if !Self::should_message_be_archived(&msg) {
                     return Ok(None);
                 }
 
                 Ok(Some(ArchivedMessage {
-                    author: Mention::from(msg.author.id),
+                    author: msg.author.id,
+                    author_name: msg.author.name.clone(),
                     content: msg.content_safe(&cache).await,
-                    video_id: stream_id,
+                    video_id: stream_id.cloned(),
                     timestamp: msg.timestamp - stream_start,
                     attachment_urls: msg.attachments.iter().map(|a| a.url.clone()).collect(),
                 }))
             })
-            .map_ok(|msg| msg.to_string())
-            .try_collect::<Vec<String>>()
+            .try_collect::<Vec<ArchivedMessage>>()
             .await?;
 
         if messages.is_empty() {
             channel.delete(&http).await?;
-            return Ok(());
+            return Ok(None);
+        }
+
+        // Messages are fetched newest first.
+        messages.reverse();
+
+        let archive = ChatArchive {
+            id: stream.map_or_else(|| channel.to_string(), |s| s.url.clone()),
+            video_id: stream.map(|s| s.url.clone()),
+            title: stream.map_or_else(|| "Unknown stream".to_owned(), |s| s.title.clone()),
+            talent: stream.map(|s| s.streamer.name.clone()),
+            started_at: stream_start,
+            archived_at: Utc::now(),
+        };
+
+        // The chat is still posted to the log channel if it can't be saved for searching.
+        if let Err(e) = config
+            .get_database_handle()
+            .and_then(|handle| archive.save(&handle, &messages))
+        {
+            error!("{:?}", e);
+        }
+
+        let log_ch = log_channel.lock().await;
+
+        let log_link = match config.chat_log_export {
+            ChatLogExport::Files => {
+                match Self::post_log_files(ctx, *log_ch, stream, &archive, &messages).await? {
+                    Some(link) => link,
+                    None => Self::post_log_embeds(ctx, *log_ch, stream, &messages).await?,
+                }
+            }
+            ChatLogExport::Embeds => Self::post_log_embeds(ctx, *log_ch, stream, &messages).await?,
+        };
+
+        drop(log_ch);
+
+        channel.delete(&http).await?;
+
+        Ok(Some(log_link))
+    }
+
+    /// Posts the whole chat as Markdown and HTML files, along with a summary embed.
+    /// Returns `None` if the files are too large to upload.
+    async fn post_log_files(
+        ctx: &Arc<CacheAndHttp>,
+        log_ch: ChannelId,
+        stream: Option<&Livestream>,
+        archive: &ChatArchive,
+        messages: &[ArchivedMessage],
+    ) -> anyhow::Result<Option<String>> {
+        let markdown = chat_log::render_markdown(archive, messages);
+        let html = chat_log::render_html(archive, messages);
+
+        if markdown.len() + html.len() > ATTACHMENT_LIMIT {
+            warn!(
+                size = markdown.len() + html.len(),
+                "Chat log too large to upload, posting it as embeds instead."
+            );
+            return Ok(None);
         }
 
+        let chatters = messages.iter().map(|m| &m.author_name).unique().count();
+        let log_colour = stream.map_or(6_282_735, |s| s.streamer.colour);
+
+        let log_message = log_ch
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    e.colour(log_colour).description(format!(
+                        "{} messages from {} chatters. Open the HTML file in a browser for timestamps linked to the VOD.",
+                        messages.len(),
+                        chatters
+                    ));
+
+                    match stream {
+                        Some(stream) => e
+                            .title(format!("Logs from {}", &stream.title))
+                            .url(format!("https://youtube.com/watch?v={}", &stream.url))
+                            .thumbnail(&stream.thumbnail)
+                            .timestamp(&stream.duration.map_or_else(Utc::now, |d| {
+                                stream.start_at + chrono::Duration::seconds(d as i64)
+                            }))
+                            .author(|a| {
+                                a.name(&stream.streamer.display_name)
+                                    .url(format!(
+                                        "https://www.youtube.com/channel/{}",
+                                        &stream.streamer.channel
+                                    ))
+                                    .icon_url(&stream.streamer.icon)
+                            }),
+                        None => e.title("Logs from unknown stream").timestamp(&Utc::now()),
+                    }
+                })
+                .add_files(vec![
+                    AttachmentType::Bytes {
+                        data: Cow::from(markdown.into_bytes()),
+                        filename: format!("{}-chat.md", archive.id),
+                    },
+                    AttachmentType::Bytes {
+                        data: Cow::from(html.into_bytes()),
+                        filename: format!("{}-chat.html", archive.id),
+                    },
+                ])
+            })
+            .await
+            .context(here!())?;
+
+        Ok(Some(log_message.link()))
+    }
+
+    /// Posts the chat as embeds of up to six 1000 character chunks each, with an index message
+    /// linking to them if there's more than one. Messages too long for a chunk are left out.
+    async fn post_log_embeds(
+        ctx: &Arc<CacheAndHttp>,
+        log_ch: ChannelId,
+        stream: Option<&Livestream>,
+        messages: &[ArchivedMessage],
+    ) -> anyhow::Result<String> {
+        let http = &ctx.http;
+
         let message_chunks = messages
-            .into_iter()
-            .rev()
+            .iter()
+            .filter(|m| m.content.len() <= 1000)
+            .map(ToString::to_string)
             .coalesce(|a, b| {
                 if a.len() + b.len() <= 1000 {
                     Ok(a + &b)
@@ -656,11 +1704,10 @@ impl DiscordApi {
             })
             .collect::<Vec<String>>();
 
-        let log_ch = log_channel.lock().await;
-        let log_colour = stream.as_ref().map_or(6_282_735, |s| s.streamer.colour);
+        let log_colour = stream.map_or(6_282_735, |s| s.streamer.colour);
 
         if message_chunks.len() < 6 {
-            if let Some(stream) = stream {
+            let log_message = if let Some(stream) = stream {
                 log_ch
                     .send_message(&http, |m| {
                         m.embed(|e| {
@@ -696,8 +1743,7 @@ impl DiscordApi {
                     .await?
             };
 
-            channel.delete(&http).await?;
-            return Ok(());
+            return Ok(log_message.link());
         }
 
         let mut index = log_ch
@@ -721,8 +1767,6 @@ impl DiscordApi {
             );
         }
 
-        drop(log_ch);
-
         let table_of_contents = log_message_links
             .into_iter()
             .enumerate()
@@ -731,7 +1775,7 @@ impl DiscordApi {
 
         if let Some(stream) = stream {
             index
-                .edit(&ctx, |e| {
+                .edit(ctx, |e| {
                     e.content("").embed(|e| {
                         e.colour(log_colour)
                             .title(format!("Logs from {}", &stream.title))
@@ -754,7 +1798,7 @@ impl DiscordApi {
                 .await?
         } else {
             index
-                .edit(&ctx, |e| {
+                .edit(ctx, |e| {
                     e.content("").embed(|e| {
                         e.colour(log_colour)
                             .title("Logs from unknown stream")
@@ -765,9 +1809,7 @@ impl DiscordApi {
                 .await?
         };
 
-        channel.delete(&http).await?;
-
-        Ok(())
+        Ok(index.link())
     }
 
     fn should_message_be_archived(msg: &Message) -> bool {
@@ -779,10 +1821,6 @@ impl DiscordApi {
             return false;
         }
 
-        if msg.content.len() > 1000 {
-            return false;
-        }
-
         match msg.kind {
             MessageType::Regular | MessageType::InlineReply => (),
             _ => return false,
@@ -852,66 +1890,121 @@ impl DiscordApi {
     }
 }
 
+/// Whether the request failed because the message or channel no longer exists.
+fn is_not_found(error: &serenity::Error) -> bool {
+    match error {
+        serenity::Error::Http(e) => matches!(
+            &**e,
+            HttpError::UnsuccessfulRequest(response) if response.status_code == StatusCode::NOT_FOUND
+        ),
+        _ => false,
+    }
+}
+
+/// Which text a tweet post shows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumString, ToString, EnumIter)]
+pub enum TweetView {
+    Original,
+    Translation,
+    Both,
+}
+
+impl TweetView {
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Original => "Original",
+            Self::Translation => "Translation",
+            Self::Both => "Both",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum DiscordMessageData {
     Tweet(HoloTweet),
+    Fanart(HoloFanart),
+    Space(HoloSpace),
     ScheduledLive(Livestream),
     ScheduleUpdate(ScheduleUpdate),
     Birthday(Birthday),
     Reminder(Reminder),
 }
 
-struct ArchivedMessage<'a> {
-    pub author: Mention,
-    pub content: String,
-    pub timestamp: Duration,
-    pub attachment_urls: Vec<String>,
-    pub video_id: Option<&'a String>,
+/// What to keep track of once a queued post has been delivered. Stored along with the post
+/// in the outbox, so posts delivered after a restart are tracked as well.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+enum Delivery {
+    Tweet {
+        tweet_id: u64,
+        author: String,
+        text: String,
+        translation: Option<String>,
+    },
+    Live {
+        video_id: String,
+    },
 }
 
-impl ArchivedMessage<'_> {
-    pub fn format_timestamp(&self) -> String {
-        let hours = (self.timestamp.num_hours() != 0)
-            .then(|| format!("{:02}:", self.timestamp.num_hours().abs()))
-            .unwrap_or_default();
-
-        let minutes = self.timestamp.num_minutes() % 60;
-        let seconds = self.timestamp.num_seconds() % 60;
-
-        // Check if message was sent before the stream started.
-        if self.timestamp.num_seconds() < 0 {
-            format!("-{}{:02}:{:02}", hours, minutes.abs(), seconds.abs())
-        } else {
-            let timestamp = format!("{}{:02}:{:02}", hours, minutes, seconds);
-
-            if let Some(id) = &self.video_id {
-                let url = format!(
-                    "https://youtu.be/{id}?t={secs}",
-                    id = id,
-                    secs = self.timestamp.num_seconds()
-                );
-                format!("[{time}]({url})", time = timestamp, url = url)
-            } else {
-                timestamp
+impl Delivery {
+    /// Saves what's needed to update the post later on.
+    fn record(&self, handle: &Connection, message: &Message) -> anyhow::Result<()> {
+        match self {
+            // Keep both texts around, so the post can be switched between them later.
+            Self::Tweet {
+                text,
+                translation: Some(translation),
+                ..
+            } => DiscordApi::save_translated_tweet(handle, message.id, text, translation),
+            Self::Tweet { .. } => Ok(()),
+            Self::Live { video_id } => {
+                DiscordApi::save_live_notification(handle, video_id, message)
             }
         }
     }
 }
 
-impl std::fmt::Display for ArchivedMessage<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        writeln!(
-            f,
-            "{} {}: {}",
-            self.format_timestamp(),
-            self.author,
-            self.content
-        )?;
-
-        if !self.attachment_urls.is_empty() {
-            writeln!(f, "{}", self.attachment_urls.join(" "))
-        } else {
-            Ok(())
+struct CachedTweet {
+    author: String,
+    text: String,
+    messages: Vec<PostedTweet>,
+}
+
+struct PostedTweet {
+    reference: MessageReference,
+    link: String,
+}
+
+struct CrossChannelReply {
+    author: String,
+    link: String,
+    text: String,
+}
+
+impl CrossChannelReply {
+    const QUOTE_LENGTH: usize = 100;
+
+    fn format(&self, include_quote: bool) -> String {
+        let jump_link = format!("[Jump to tweet]({})", self.link);
+
+        if !include_quote {
+            return jump_link;
+        }
+
+        let mut quote = self
+            .text
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .take(Self::QUOTE_LENGTH)
+            .collect::<String>();
+
+        if quote.len() < self.text.len() {
+            quote.push('…');
         }
+
+        format!("> {}\n{}", quote, jump_link)
     }
 }
@@ -0,0 +1,136 @@
+use utility::config::{ArchivedMessage, ChatArchive};
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; background: #36393f; color: #dcddde; }
+a { color: #00aff4; }
+ol { list-style: none; padding: 0; }
+li { padding: 0.2em 0; overflow-wrap: anywhere; }
+.time { font-family: monospace; margin-right: 0.5em; }
+.author { font-weight: bold; color: #fff; margin-right: 0.5em; }
+.content { white-space: pre-wrap; }";
+
+/// Renders the chat as Markdown, with the timestamps linked to the VOD.
+pub fn render_markdown(archive: &ChatArchive, messages: &[ArchivedMessage]) -> String {
+    let mut details = Vec::with_capacity(3);
+
+    if let Some(talent) = &archive.talent {
+        details.push(talent.clone());
+    }
+
+    if let Some(video_id) = &archive.video_id {
+        details.push(format!("<https://youtube.com/watch?v={}>", video_id));
+    }
+
+    details.push(format!(
+        "started {}",
+        archive.started_at.format("%Y-%m-%d %H:%M UTC")
+    ));
+
+    let mut log = format!("# {}\n\n{}\n\n", archive.title, details.join(", "));
+
+    for message in messages {
+        log.push_str(&format!(
+            "{} **{}**: {}\n",
+            message.format_timestamp(),
+            message.author_name,
+            message.content
+        ));
+
+        for url in &message.attachment_urls {
+            log.push_str(&format!("  - <{}>\n", url));
+        }
+    }
+
+    log
+}
+
+/// Renders the chat as a self-contained HTML page, with the timestamps linked to the VOD.
+pub fn render_html(archive: &ChatArchive, messages: &[ArchivedMessage]) -> String {
+    let title = escape_html(&archive.title);
+
+    let mut heading = match &archive.video_id {
+        Some(video_id) => format!(
+            "<h1><a href=\"https://youtube.com/watch?v={}\">{}</a></h1>\n<p>",
+            escape_html(video_id),
+            title
+        ),
+        None => format!("<h1>{}</h1>\n<p>", title),
+    };
+
+    if let Some(talent) = &archive.talent {
+        heading.push_str(&format!("{}, ", escape_html(talent)));
+    }
+
+    heading.push_str(&format!(
+        "started {}</p>",
+        archive.started_at.format("%Y-%m-%d %H:%M UTC")
+    ));
+
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}\n<ol>\n",
+        title, HTML_STYLE, heading
+    );
+
+    for message in messages {
+        let time = match message.vod_url() {
+            Some(url) => format!(
+                "<a class=\"time\" href=\"{}\">{}</a>",
+                escape_html(&url),
+                message.plain_timestamp()
+            ),
+            None => format!("<span class=\"time\">{}</span>", message.plain_timestamp()),
+        };
+
+        let attachments = message
+            .attachment_urls
+            .iter()
+            .map(|url| format!(" <a href=\"{0}\">{0}</a>", escape_html(url)))
+            .collect::<String>();
+
+        page.push_str(&format!(
+            "<li>{}<span class=\"author\">{}</span><span class=\"content\">{}</span>{}</li>\n",
+            time,
+            escape_html(&message.author_name),
+            escape_html(&message.content),
+            attachments
+        ));
+    }
+
+    page.push_str("</ol>\n</body>\n</html>\n");
+    page
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn leaves_plain_text_alone() {
+        assert_eq!(escape_html("おつぺこ! 🥕"), "おつぺこ! 🥕");
+        assert_eq!(escape_html(""), "");
+    }
+}
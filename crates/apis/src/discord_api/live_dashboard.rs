@@ -0,0 +1,216 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Context;
+use chrono::Utc;
+use itertools::Itertools;
+use serenity::{
+    builder::CreateEmbed,
+    model::{
+        id::{ChannelId, MessageId},
+        misc::Mention,
+    },
+    CacheAndHttp,
+};
+use tokio::{sync::watch, time::Instant};
+use tracing::{error, info, instrument, warn};
+
+use utility::here;
+
+use super::{is_not_found, DiscordApi};
+use crate::holo_api::{Livestream, StreamState};
+
+/// Title of the dashboard, used to find it again after a restart.
+const DASHBOARD_TITLE: &str = "Live now";
+/// Minimum time between edits of the dashboard, to stay clear of Discord's rate limits.
+const EDIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// Number of upcoming streams listed.
+const UPCOMING_COUNT: usize = 10;
+/// Discord's limit on the length of embed descriptions, minus room for the section headings.
+const DESCRIPTION_LIMIT: usize = 4000;
+
+impl DiscordApi {
+    /// Keeps a pinned message up to date with who is live and who is going live next.
+    /// Changes are batched, so the message is edited at most once per [`EDIT_INTERVAL`].
+    #[instrument(skip(ctx, index_receiver, claimed_receiver))]
+    pub(super) async fn live_dashboard_thread(
+        ctx: Arc<CacheAndHttp>,
+        channel: ChannelId,
+        mut index_receiver: watch::Receiver<HashMap<u32, Livestream>>,
+        mut claimed_receiver: watch::Receiver<HashMap<u32, ChannelId>>,
+    ) -> anyhow::Result<()> {
+        let mut dashboard = match Self::find_dashboard(&ctx, channel).await {
+            Ok(d) => d,
+            Err(e) => {
+                error!("{:?}", e);
+                None
+            }
+        };
+        let mut shown = None;
+
+        loop {
+            let description = {
+                let index = index_receiver.borrow();
+                let claimed = claimed_receiver.borrow();
+
+                Self::dashboard_description(&index, &claimed)
+            };
+
+            if shown.as_ref() != Some(&description) {
+                match Self::show_dashboard(&ctx, channel, dashboard, &description).await {
+                    Ok(message) => {
+                        dashboard = Some(message);
+                        shown = Some(description);
+                    }
+                    Err(e) => error!("{:?}", e),
+                }
+            }
+
+            let last_edit = Instant::now();
+
+            tokio::select! {
+                res = index_receiver.changed() => res.context(here!())?,
+                res = claimed_receiver.changed() => res.context(here!())?,
+            }
+
+            // Anything that changes while waiting is included in the next edit.
+            tokio::time::sleep_until(last_edit + EDIT_INTERVAL).await;
+        }
+    }
+
+    /// Finds the dashboard posted before the bot was restarted.
+    async fn find_dashboard(
+        ctx: &Arc<CacheAndHttp>,
+        channel: ChannelId,
+    ) -> anyhow::Result<Option<MessageId>> {
+        let bot = ctx.cache.current_user_id().await;
+        let pins = channel.pins(&ctx.http).await.context(here!())?;
+
+        Ok(pins
+            .into_iter()
+            .find(|m| {
+                m.author.id == bot
+                    && m.embeds.first().and_then(|e| e.title.as_deref()) == Some(DASHBOARD_TITLE)
+            })
+            .map(|m| m.id))
+    }
+
+    /// Edits the dashboard, or posts and pins a new one if there isn't one or it was deleted.
+    async fn show_dashboard(
+        ctx: &Arc<CacheAndHttp>,
+        channel: ChannelId,
+        dashboard: Option<MessageId>,
+        description: &str,
+    ) -> anyhow::Result<MessageId> {
+        let mut embed = CreateEmbed::default();
+        embed
+            .title(DASHBOARD_TITLE)
+            .description(description)
+            .footer(|f| f.text("Last updated"))
+            .timestamp(&Utc::now());
+
+        if let Some(message) = dashboard {
+            let edited = channel
+                .edit_message(&ctx.http, message, |m| {
+                    m.embed(|e| {
+                        *e = embed.clone();
+                        e
+                    })
+                })
+                .await;
+
+            match edited {
+                Ok(_) => return Ok(message),
+                Err(e) if is_not_found(&e) => {
+                    warn!("The live dashboard is gone, posting a new one.");
+
+                    // Make sure the old one isn't left pinned, or it could be found
+                    // instead of the new one after a restart.
+                    let _ = channel.unpin(&ctx.http, message).await;
+                }
+                Err(e) => return Err(e).context(here!()),
+            }
+        }
+
+        info!("Posting live dashboard.");
+
+        let message = channel
+            .send_message(&ctx.http, |m| m.set_embed(embed))
+            .await
+            .context(here!())?;
+
+        // The dashboard is still kept up to date if it can't be pinned.
+        if let Err(e) = message.pin(&ctx.http).await {
+            warn!(?e, "Couldn't pin the live dashboard.");
+        }
+
+        Ok(message.id)
+    }
+
+    /// Lists the streams that are live along with their chat channels, followed by the next
+    /// streams to start. Discord's relative timestamps keep the durations current between edits.
+    fn dashboard_description(
+        index: &HashMap<u32, Livestream>,
+        claimed: &HashMap<u32, ChannelId>,
+    ) -> String {
+        let live = index
+            .values()
+            .filter(|s| s.state == StreamState::Live)
+            .sorted_by_key(|s| s.start_at)
+            .map(|s| {
+                let chat = claimed
+                    .get(&s.id)
+                    .map(|c| format!(" | {}", Mention::from(*c)))
+                    .unwrap_or_default();
+
+                format!(
+                    "🔴 **{}**: [{}](https://youtube.com/watch?v={})\nStarted <t:{}:R>{}",
+                    s.streamer.display_name,
+                    s.title,
+                    s.url,
+                    s.start_at.timestamp(),
+                    chat
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let upcoming = index
+            .values()
+            .filter(|s| s.state == StreamState::Scheduled)
+            .sorted_by_key(|s| s.start_at)
+            .take(UPCOMING_COUNT)
+            .map(|s| {
+                format!(
+                    "**{}**: [{}](https://youtube.com/watch?v={}) <t:{}:R>",
+                    s.streamer.display_name,
+                    s.title,
+                    s.url,
+                    s.start_at.timestamp()
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let upcoming = if upcoming.is_empty() {
+            "No streams are scheduled.".to_owned()
+        } else {
+            upcoming.join("\n")
+        };
+
+        let mut live_text = String::new();
+
+        for (i, line) in live.iter().enumerate() {
+            if live_text.len() + line.len() + upcoming.len() > DESCRIPTION_LIMIT {
+                live_text.push_str(&format!("...and {} more.\n", live.len() - i));
+                break;
+            }
+
+            live_text.push_str(line);
+            live_text.push('\n');
+        }
+
+        if live.is_empty() {
+            live_text.push_str("Nobody is live right now.\n");
+        }
+
+        format!("**Live**\n{}\n**Starting next**\n{}", live_text, upcoming)
+    }
+}
@@ -0,0 +1,546 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::StatusCode;
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use serenity::{
+    builder::CreateMessage,
+    http::{Http, HttpError},
+    model::{channel::Message, id::ChannelId, webhook::Webhook},
+    utils::hashmap_to_json_map,
+};
+use tokio::sync::{broadcast, oneshot, Mutex, Notify};
+use tracing::{debug, debug_span, error, instrument, warn, Instrument};
+
+use utility::{config::Config, here};
+
+use super::Delivery;
+
+/// How long a sender waits for its message to be delivered before leaving it to the queue.
+const DELIVERY_WAIT: std::time::Duration = std::time::Duration::from_secs(10);
+/// How often the queue is checked for messages that are due for another attempt.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// Number of attempts after which a message is given up on.
+const MAX_ATTEMPTS: u32 = 20;
+/// How long delivered messages are kept in the queue table.
+const DELIVERED_RETENTION_DAYS: i64 = 7;
+/// Name of the webhooks the bot creates and reuses.
+const WEBHOOK_NAME: &str = "HoloBot";
+/// Number of delivered posts kept for the posting thread if it falls behind.
+const DELIVERED_CAPACITY: usize = 64;
+
+/// Durable queue of messages waiting to be posted to Discord.
+///
+/// Messages are stored in the database before they're sent, so they survive Discord
+/// outages and restarts. Messages to the same channel are delivered in the order they were
+/// queued, while each channel's queue is delivered independently of the others.
+pub struct Outbox {
+    http: Arc<Http>,
+    database: Mutex<Connection>,
+    waiters: Mutex<HashMap<i64, oneshot::Sender<Message>>>,
+    queued: Notify,
+    /// Channels whose queue is being worked through by a task.
+    delivering: Mutex<HashSet<ChannelId>>,
+    delivered: broadcast::Sender<(Delivery, Message)>,
+    /// Webhook of each channel, or `None` if the bot isn't allowed to manage them there.
+    webhooks: Mutex<HashMap<ChannelId, Option<Webhook>>>,
+}
+
+/// Name and avatar to post a message under, using a channel webhook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookAuthor {
+    pub name: String,
+    pub avatar: Option<String>,
+}
+
+#[derive(Debug)]
+struct QueuedMessage {
+    id: i64,
+    channel: ChannelId,
+    payload: Value,
+    author: Option<WebhookAuthor>,
+    delivery: Option<Delivery>,
+    attempts: u32,
+    next_attempt_at: DateTime<Utc>,
+}
+
+impl Outbox {
+    pub fn new(config: &Config, http: Arc<Http>) -> anyhow::Result<Self> {
+        let database = config.get_database_handle()?;
+
+        database
+            .execute(
+                "DELETE FROM DiscordOutbox WHERE delivered_at IS NOT NULL AND created_at < ?",
+                [Utc::now() - Duration::days(DELIVERED_RETENTION_DAYS)],
+            )
+            .context(here!())?;
+
+        Ok(Self {
+            http,
+            database: Mutex::new(database),
+            waiters: Mutex::new(HashMap::new()),
+            queued: Notify::new(),
+            delivering: Mutex::new(HashSet::new()),
+            delivered: broadcast::channel(DELIVERED_CAPACITY).0,
+            webhooks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Queues a message and waits a short while for it to be delivered.
+    ///
+    /// Returns `None` if the message couldn't be delivered yet and has been left
+    /// in the queue to be retried later.
+    pub async fn send<'a, F>(&self, channel: ChannelId, f: F) -> anyhow::Result<Option<Message>>
+    where
+        for<'b> F: FnOnce(&'b mut CreateMessage<'a>) -> &'b mut CreateMessage<'a>,
+    {
+        self.send_as(channel, None, f).await
+    }
+
+    /// Same as [`Outbox::send`], but posts the message under another name and avatar if an
+    /// author is given. Falls back to a regular message if the channel's webhook can't be used.
+    /// Webhooks can't reply to messages, so message references are left out.
+    pub async fn send_as<'a, F>(
+        &self,
+        channel: ChannelId,
+        author: Option<&WebhookAuthor>,
+        f: F,
+    ) -> anyhow::Result<Option<Message>>
+    where
+        for<'b> F: FnOnce(&'b mut CreateMessage<'a>) -> &'b mut CreateMessage<'a>,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let id = self.queue(channel, author, None, f, Some(sender)).await?;
+
+        match tokio::time::timeout(DELIVERY_WAIT, receiver).await {
+            Ok(Ok(message)) => Ok(Some(message)),
+            Ok(Err(_)) => Err(
+                anyhow!("Message {} to {} couldn't be delivered.", id, channel).context(here!()),
+            ),
+            Err(_) => {
+                warn!(%id, %channel, "Message not delivered yet, leaving it in the queue.");
+                self.waiters.lock().await.remove(&id);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Queues a message without waiting for it to be delivered.
+    pub async fn enqueue<'a, F>(&self, channel: ChannelId, f: F) -> anyhow::Result<()>
+    where
+        for<'b> F: FnOnce(&'b mut CreateMessage<'a>) -> &'b mut CreateMessage<'a>,
+    {
+        self.queue(channel, None, None, f, None).await.map(|_| ())
+    }
+
+    /// Queues a message without waiting for it to be delivered, posting it under another name
+    /// and avatar if an author is given, like [`Outbox::send_as`].
+    ///
+    /// The delivery is stored along with the message and recorded once the message is
+    /// delivered, however long that takes and even if the bot restarts in between.
+    pub(super) async fn enqueue_as<'a, F>(
+        &self,
+        channel: ChannelId,
+        author: Option<&WebhookAuthor>,
+        delivery: Option<Delivery>,
+        f: F,
+    ) -> anyhow::Result<()>
+    where
+        for<'b> F: FnOnce(&'b mut CreateMessage<'a>) -> &'b mut CreateMessage<'a>,
+    {
+        self.queue(channel, author, delivery.as_ref(), f, None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Delivered messages that had a delivery attached, for the posting thread to keep track of.
+    pub(super) fn deliveries(&self) -> broadcast::Receiver<(Delivery, Message)> {
+        self.delivered.subscribe()
+    }
+
+    async fn queue<'a, F>(
+        &self,
+        channel: ChannelId,
+        author: Option<&WebhookAuthor>,
+        delivery: Option<&Delivery>,
+        f: F,
+        waiter: Option<oneshot::Sender<Message>>,
+    ) -> anyhow::Result<i64>
+    where
+        for<'b> F: FnOnce(&'b mut CreateMessage<'a>) -> &'b mut CreateMessage<'a>,
+    {
+        let mut message = CreateMessage::default();
+        f(&mut message);
+
+        let payload = Value::Object(hashmap_to_json_map(message.0));
+        let delivery = delivery
+            .map(serde_json::to_value)
+            .transpose()
+            .context(here!())?;
+
+        let id = {
+            let database = self.database.lock().await;
+            let now = Utc::now();
+
+            database
+                .execute(
+                    "INSERT INTO DiscordOutbox (channel_id, payload, webhook_name, webhook_avatar, delivery, attempts, next_attempt_at, created_at) VALUES (?, ?, ?, ?, ?, 0, ?, ?)",
+                    params![
+                        channel.as_u64(),
+                        payload,
+                        author.map(|a| &a.name),
+                        author.and_then(|a| a.avatar.as_ref()),
+                        delivery,
+                        now,
+                        now
+                    ],
+                )
+                .context(here!())?;
+
+            let id = database.last_insert_rowid();
+
+            // Registered while the database is locked, so the delivery thread can't miss it.
+            if let Some(waiter) = waiter {
+                self.waiters.lock().await.insert(id, waiter);
+            }
+
+            id
+        };
+
+        self.queued.notify_one();
+
+        Ok(id)
+    }
+
+    /// Delivers queued messages, including the ones left over from previous runs.
+    /// Each channel's queue is worked through by its own task, so a slow or rate limited
+    /// channel doesn't hold up the others.
+    #[instrument(skip(self))]
+    pub async fn delivery_thread(self: Arc<Self>) {
+        loop {
+            if let Err(e) = self.start_due_channels().await {
+                error!("{:?}", e);
+            }
+
+            tokio::select! {
+                _ = self.queued.notified() => {},
+                _ = tokio::time::sleep(POLL_INTERVAL) => {},
+            }
+        }
+    }
+
+    /// Starts a delivery task for every channel whose oldest message is due.
+    async fn start_due_channels(self: &Arc<Self>) -> anyhow::Result<()> {
+        let now = Utc::now();
+
+        // Only the oldest message of a channel decides whether its queue can move on.
+        let mut heads = HashMap::new();
+
+        for message in self.pending(None).await? {
+            heads
+                .entry(message.channel)
+                .or_insert(message.next_attempt_at);
+        }
+
+        let mut delivering = self.delivering.lock().await;
+
+        for (channel, next_attempt_at) in heads {
+            if next_attempt_at > now || !delivering.insert(channel) {
+                continue;
+            }
+
+            let outbox = Arc::clone(self);
+
+            tokio::spawn(
+                async move { outbox.deliver_channel(channel).await }
+                    .instrument(debug_span!("Outbox channel delivery", %channel)),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Delivers the channel's messages in order, until its queue is empty or the oldest
+    /// message has to wait for another attempt.
+    async fn deliver_channel(self: Arc<Self>, channel: ChannelId) {
+        let emptied = loop {
+            let message = match self.pending(Some(channel)).await {
+                Ok(pending) => match pending.into_iter().next() {
+                    Some(m) if m.next_attempt_at <= Utc::now() => m,
+                    Some(_) => break false,
+                    None => break true,
+                },
+                Err(e) => {
+                    error!("{:?}", e);
+                    break false;
+                }
+            };
+
+            match self.deliver(message).await {
+                Ok(true) => (),
+                Ok(false) => break false,
+                Err(e) => {
+                    error!("{:?}", e);
+                    break false;
+                }
+            }
+        };
+
+        self.delivering.lock().await.remove(&channel);
+
+        // Messages queued while the task was finishing up were skipped, so look again.
+        if emptied {
+            self.queued.notify_one();
+        }
+    }
+
+    /// Messages waiting to be delivered, oldest first, to one channel or to all of them.
+    async fn pending(&self, channel: Option<ChannelId>) -> anyhow::Result<Vec<QueuedMessage>> {
+        let database = self.database.lock().await;
+
+        let mut stmt = database
+            .prepare(
+                "SELECT id, channel_id, payload, webhook_name, webhook_avatar, attempts, next_attempt_at, delivery FROM DiscordOutbox WHERE delivered_at IS NULL AND failed_at IS NULL AND (?1 IS NULL OR channel_id = ?1) ORDER BY id",
+            )
+            .context(here!())?;
+
+        let messages = stmt
+            .query_map([channel.map(|c| *c.as_u64())], |row| {
+                Ok(QueuedMessage {
+                    id: row.get(0)?,
+                    channel: ChannelId(row.get(1)?),
+                    payload: row.get(2)?,
+                    author: row.get::<_, Option<String>>(3)?.map(|name| WebhookAuthor {
+                        name,
+                        avatar: row.get(4).ok().flatten(),
+                    }),
+                    attempts: row.get(5)?,
+                    next_attempt_at: row.get(6)?,
+                    delivery: row.get::<_, Option<Value>>(7)?.and_then(|d| {
+                        match serde_json::from_value(d) {
+                            Ok(delivery) => Some(delivery),
+                            Err(e) => {
+                                warn!("Couldn't read the delivery of a queued message: {:?}", e);
+                                None
+                            }
+                        }
+                    }),
+                })
+            })
+            .context(here!())?
+            .collect::<Result<Vec<_>, _>>()
+            .context(here!())?;
+
+        Ok(messages)
+    }
+
+    /// Tries to send the message once. Returns whether the message has left the queue,
+    /// either because it was delivered or because it was given up on.
+    async fn deliver(&self, message: QueuedMessage) -> anyhow::Result<bool> {
+        let webhook_result = match &message.author {
+            Some(author) => self.send_with_webhook(&message, author).await,
+            None => None,
+        };
+
+        let result = match webhook_result {
+            Some(result) => result,
+            None => {
+                self.http
+                    .send_message(*message.channel.as_u64(), &message.payload)
+                    .await
+            }
+        };
+
+        let attempts = message.attempts + 1;
+        let database = self.database.lock().await;
+
+        match result {
+            Ok(sent) => {
+                debug!(id = message.id, channel = %message.channel, "Message delivered.");
+
+                database
+                    .execute(
+                        "UPDATE DiscordOutbox SET attempts = ?, delivered_at = ?, message_id = ? WHERE id = ?",
+                        params![attempts, Utc::now(), sent.id.as_u64(), message.id],
+                    )
+                    .context(here!())?;
+
+                if let Some(delivery) = message.delivery {
+                    if let Err(e) = delivery.record(&database, &sent) {
+                        error!("{:?}", e);
+                    }
+
+                    // The posting thread might not be listening, such as during shutdown.
+                    let _ = self.delivered.send((delivery, sent.clone()));
+                }
+
+                if let Some(waiter) = self.waiters.lock().await.remove(&message.id) {
+                    waiter.send(sent).ok();
+                }
+
+                Ok(true)
+            }
+            Err(e) if is_permanent(&e) || attempts >= MAX_ATTEMPTS => {
+                error!(id = message.id, channel = %message.channel, attempts, "Giving up on message: {:?}", e);
+
+                database
+                    .execute(
+                        "UPDATE DiscordOutbox SET attempts = ?, failed_at = ?, last_error = ? WHERE id = ?",
+                        params![attempts, Utc::now(), e.to_string(), message.id],
+                    )
+                    .context(here!())?;
+
+                // Dropping the waiter lets the sender know the message won't arrive.
+                self.waiters.lock().await.remove(&message.id);
+
+                Ok(true)
+            }
+            Err(e) => {
+                let next_attempt_at = Utc::now() + retry_delay(attempts);
+                warn!(id = message.id, channel = %message.channel, attempts, %next_attempt_at, "Failed to send message: {:?}", e);
+
+                database
+                    .execute(
+                        "UPDATE DiscordOutbox SET attempts = ?, next_attempt_at = ?, last_error = ? WHERE id = ?",
+                        params![attempts, next_attempt_at, e.to_string(), message.id],
+                    )
+                    .context(here!())?;
+
+                Ok(false)
+            }
+        }
+    }
+
+    /// Posts the message through the channel's webhook.
+    /// Returns `None` if the webhook can't be used and a regular message should be sent instead.
+    async fn send_with_webhook(
+        &self,
+        message: &QueuedMessage,
+        author: &WebhookAuthor,
+    ) -> Option<serenity::Result<Message>> {
+        let webhook = self.webhook(message.channel).await?;
+        let token = webhook.token.as_ref()?;
+
+        let mut payload = match &message.payload {
+            Value::Object(map) => map.clone(),
+            _ => return None,
+        };
+
+        // Webhooks only take lists of embeds and can't reply to messages.
+        if let Some(embed) = payload.remove("embed") {
+            payload.insert("embeds".to_owned(), Value::Array(vec![embed]));
+        }
+        payload.remove("message_reference");
+
+        payload.insert("username".to_owned(), Value::String(author.name.clone()));
+
+        if let Some(avatar) = &author.avatar {
+            payload.insert("avatar_url".to_owned(), Value::String(avatar.clone()));
+        }
+
+        match self
+            .http
+            .execute_webhook(*webhook.id.as_u64(), token, true, &payload)
+            .await
+        {
+            Ok(Some(sent)) => Some(Ok(sent)),
+            Ok(None) => Some(Err(serenity::Error::Other(
+                "Webhook didn't return the message.",
+            ))),
+            // The webhook may have been deleted, so look it up again next time.
+            Err(e) if is_permanent(&e) => {
+                warn!(channel = %message.channel, "Webhook failed, sending a regular message: {:?}", e);
+                self.webhooks.lock().await.remove(&message.channel);
+                None
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Finds the bot's webhook in the channel, creating it if needed.
+    async fn webhook(&self, channel: ChannelId) -> Option<Webhook> {
+        let mut webhooks = self.webhooks.lock().await;
+
+        if let Some(webhook) = webhooks.get(&channel) {
+            return webhook.clone();
+        }
+
+        let webhook = match channel.webhooks(&self.http).await {
+            Ok(existing) => match existing
+                .into_iter()
+                .find(|w| w.name.as_deref() == Some(WEBHOOK_NAME) && w.token.is_some())
+            {
+                Some(webhook) => Ok(webhook),
+                None => channel.create_webhook(&self.http, WEBHOOK_NAME).await,
+            },
+            Err(e) => Err(e),
+        };
+
+        match webhook {
+            Ok(webhook) => {
+                webhooks.insert(channel, Some(webhook.clone()));
+                Some(webhook)
+            }
+            // Missing permissions won't fix themselves, so don't keep asking.
+            Err(e) if is_permanent(&e) => {
+                warn!(%channel, "Can't use webhooks, sending regular messages instead: {:?}", e);
+                webhooks.insert(channel, None);
+                None
+            }
+            Err(e) => {
+                warn!(%channel, "Couldn't get webhook: {:?}", e);
+                None
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Outbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Outbox").finish()
+    }
+}
+
+/// Exponential backoff starting at 5 seconds, capped at 10 minutes.
+fn retry_delay(attempts: u32) -> Duration {
+    (Duration::seconds(5) * 2_i32.pow(attempts.min(8) - 1)).min(Duration::minutes(10))
+}
+
+/// Whether retrying the request can't possibly make it succeed, such as when
+/// the bot lacks access to the channel or the message is malformed.
+fn is_permanent(error: &serenity::Error) -> bool {
+    match error {
+        serenity::Error::Http(e) => match &**e {
+            HttpError::UnsuccessfulRequest(response) => {
+                response.status_code.is_client_error()
+                    && response.status_code != StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        },
+        serenity::Error::Json(_) | serenity::Error::Model(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles() {
+        assert_eq!(retry_delay(1), Duration::seconds(5));
+        assert_eq!(retry_delay(2), Duration::seconds(10));
+        assert_eq!(retry_delay(7), Duration::seconds(320));
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay(8), Duration::minutes(10));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), Duration::minutes(10));
+    }
+}
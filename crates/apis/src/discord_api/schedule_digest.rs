@@ -0,0 +1,246 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, Timelike, Utc};
+use futures::FutureExt;
+use itertools::Itertools;
+use serenity::{builder::CreateEmbed, CacheAndHttp};
+use tokio::sync::{mpsc, watch};
+use tracing::{error, info, instrument};
+
+use utility::{
+    config::{Config, LoadFromDatabase, ScheduleDigest},
+    here,
+};
+
+use super::{DiscordApi, Outbox};
+use crate::holo_api::{Livestream, StreamState, StreamUpdate};
+
+/// How often the digests are checked for being due.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// How long to wait for more schedule changes before updating the digests, as streams tend to
+/// be scheduled in bursts.
+const UPDATE_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(10);
+/// Discord's limit on the length of embed descriptions.
+const DESCRIPTION_LIMIT: usize = 4096;
+
+impl DiscordApi {
+    /// Posts each guild's daily schedule digest when it's due, and keeps the latest one up to
+    /// date as streams are scheduled or rescheduled.
+    #[instrument(skip(ctx, config, outbox, index_receiver, schedule_updates))]
+    pub(super) async fn schedule_digest_thread(
+        ctx: Arc<CacheAndHttp>,
+        config: Config,
+        outbox: Arc<Outbox>,
+        index_receiver: watch::Receiver<HashMap<u32, Livestream>>,
+        mut schedule_updates: mpsc::UnboundedReceiver<StreamUpdate>,
+    ) -> anyhow::Result<()> {
+        let handle = config.get_database_handle()?;
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            // Scheduled streams, or `None` for cancelled ones.
+            let mut updated = HashMap::new();
+
+            tokio::select! {
+                _ = interval.tick() => {},
+                update = schedule_updates.recv() => {
+                    match update {
+                        Some(update) => Self::track_schedule_update(&mut updated, update),
+                        None => return Ok(()),
+                    };
+
+                    tokio::time::sleep(UPDATE_DEBOUNCE).await;
+
+                    while let Some(Some(update)) = schedule_updates.recv().now_or_never() {
+                        Self::track_schedule_update(&mut updated, update);
+                    }
+                }
+            }
+
+            // The index might not have caught up with the updates yet.
+            let mut index = index_receiver.borrow().clone();
+
+            for (id, stream) in &updated {
+                match stream {
+                    Some(stream) => index.insert(*id, stream.clone()),
+                    None => index.remove(id),
+                };
+            }
+
+            let digests = match ScheduleDigest::load_from_database(&handle) {
+                Ok(d) => d,
+                Err(e) => {
+                    error!("{:?}", e);
+                    continue;
+                }
+            };
+
+            let now = Utc::now();
+
+            for mut digest in digests {
+                let due = digest.last_due(now);
+
+                let result = match (digest.posted_for, digest.message) {
+                    (Some(posted_for), _) if posted_for >= due => {
+                        if updated.is_empty() {
+                            continue;
+                        }
+
+                        Self::update_digest(&ctx, &digest, posted_for, &index).await
+                    }
+                    _ => Self::post_digest(&outbox, &mut digest, due, &index).await,
+                };
+
+                if let Err(e) = result.and_then(|_| digest.save_progress(&handle)) {
+                    error!("{:?}", e);
+                }
+            }
+        }
+    }
+
+    fn track_schedule_update(updated: &mut HashMap<u32, Option<Livestream>>, update: StreamUpdate) {
+        match update {
+            StreamUpdate::Scheduled(stream) => {
+                updated.insert(stream.id, Some(stream));
+            }
+            StreamUpdate::Cancelled(stream) => {
+                updated.insert(stream.id, None);
+            }
+            StreamUpdate::Started(_) | StreamUpdate::Ended(_) => (),
+        }
+    }
+
+    async fn post_digest(
+        outbox: &Outbox,
+        digest: &mut ScheduleDigest,
+        due: DateTime<Utc>,
+        index: &HashMap<u32, Livestream>,
+    ) -> anyhow::Result<()> {
+        info!(guild = %digest.guild, "Posting schedule digest.");
+
+        let embed = Self::digest_embed(digest, due, index);
+        let message = outbox
+            .send(digest.channel, |m| m.set_embed(embed))
+            .await
+            .context(here!())?;
+
+        // A digest left in the queue can't be kept up to date, but it'll still be posted.
+        digest.message = message.map(|m| m.id);
+        digest.posted_for = Some(due);
+
+        Ok(())
+    }
+
+    async fn update_digest(
+        ctx: &Arc<CacheAndHttp>,
+        digest: &ScheduleDigest,
+        posted_for: DateTime<Utc>,
+        index: &HashMap<u32, Livestream>,
+    ) -> anyhow::Result<()> {
+        let message = match digest.message {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+
+        let embed = Self::digest_embed(digest, posted_for, index);
+
+        digest
+            .channel
+            .edit_message(&ctx.http, message, |m| {
+                m.embed(|e| {
+                    *e = embed;
+                    e
+                })
+            })
+            .await
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Lists the streams starting in the 24 hours after the digest was due, grouped by the
+    /// local hour they start in.
+    fn digest_embed(
+        digest: &ScheduleDigest,
+        due: DateTime<Utc>,
+        index: &HashMap<u32, Livestream>,
+    ) -> CreateEmbed {
+        let end = due + Duration::hours(24);
+
+        let streams = index
+            .values()
+            .filter(|s| s.start_at >= due && s.start_at < end)
+            .filter(|s| digest.branch.map_or(true, |b| s.streamer.branch == b))
+            .sorted_by_key(|s| s.start_at)
+            .collect::<Vec<_>>();
+
+        let by_hour = streams.iter().group_by(|s| {
+            let local = s.start_at.with_timezone(&digest.timezone);
+            s.start_at.timestamp() - i64::from(local.minute() * 60 + local.second())
+        });
+
+        let mut description = String::with_capacity(DESCRIPTION_LIMIT);
+        let mut listed = 0;
+
+        'hours: for (hour, streams) in &by_hour {
+            let mut section = format!("**<t:{}:t>**\n", hour);
+            let listed_before = listed;
+
+            for stream in streams {
+                let line = format!(
+                    "{}**{}**: [{}](https://youtube.com/watch?v={}) <t:{}:R>\n",
+                    if stream.state == StreamState::Live {
+                        "🔴 "
+                    } else {
+                        ""
+                    },
+                    stream.streamer.display_name,
+                    stream.title,
+                    stream.url,
+                    stream.start_at.timestamp()
+                );
+
+                // Leave room for the note about the streams that didn't fit.
+                if description.len() + section.len() + line.len() + 32 > DESCRIPTION_LIMIT {
+                    if listed > listed_before {
+                        description.push_str(&section);
+                    }
+                    break 'hours;
+                }
+
+                section.push_str(&line);
+                listed += 1;
+            }
+
+            description.push_str(&section);
+            description.push('\n');
+        }
+
+        if streams.is_empty() {
+            description.push_str("No streams are scheduled in the next 24 hours.");
+        } else if listed < streams.len() {
+            description.push_str(&format!("...and {} more.", streams.len() - listed));
+        }
+
+        let mut embed = CreateEmbed::default();
+
+        embed
+            .title(format!(
+                "Streams for {}",
+                due.with_timezone(&digest.timezone).format("%A, %B %-d")
+            ))
+            .description(description)
+            .timestamp(&due)
+            .footer(|f| match digest.branch {
+                Some(branch) => f.text(format!(
+                    "{} | {}",
+                    branch.to_string(),
+                    digest.timezone.name()
+                )),
+                None => f.text(digest.timezone.name()),
+            });
+
+        embed
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::{header::CONTENT_TYPE, Client, Method, Response, StatusCode};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use serde_with::{serde_as, DisplayFromStr};
+use tokio::sync::{mpsc, watch, Mutex};
+use tracing::{error, info, instrument, warn};
+
+use utility::{
+    config::{Config, ScheduledEventsConfig},
+    here,
+};
+
+use super::DiscordApi;
+use crate::{
+    holo_api::{Livestream, StreamState, StreamUpdate},
+    http,
+};
+
+const API_BASE: &str = "https://discord.com/api/v9";
+/// External events need an end time, so streams of unknown length are assumed to last this long.
+const DEFAULT_EVENT_HOURS: i64 = 2;
+/// Discord's limit on the length of event names.
+const NAME_LIMIT: usize = 100;
+
+const PRIVACY_GUILD_ONLY: u8 = 2;
+const ENTITY_TYPE_EXTERNAL: u8 = 3;
+const STATUS_ACTIVE: u8 = 2;
+const STATUS_COMPLETED: u8 = 3;
+/// Discord's error code for changes to an event that has already ended.
+const EVENT_FINISHED_CODE: u64 = 180000;
+
+#[serde_as]
+#[derive(Deserialize, Debug)]
+struct ScheduledEvent {
+    #[serde_as(as = "DisplayFromStr")]
+    id: u64,
+}
+
+/// Guild scheduled events created for upcoming streams. Serenity doesn't support scheduled
+/// events yet, so they're managed through Discord's REST API directly.
+struct StreamEvents {
+    client: Client,
+    token: String,
+    settings: ScheduledEventsConfig,
+    database: Mutex<Connection>,
+}
+
+impl DiscordApi {
+    /// Keeps a scheduled event for each upcoming stream, and moves it along as the stream
+    /// goes live and ends.
+    #[instrument(skip(config, settings, index_receiver, updates))]
+    pub(super) async fn scheduled_events_thread(
+        config: Config,
+        settings: ScheduledEventsConfig,
+        mut index_receiver: watch::Receiver<HashMap<u32, Livestream>>,
+        mut updates: mpsc::UnboundedReceiver<StreamUpdate>,
+    ) -> anyhow::Result<()> {
+        let events = StreamEvents {
+            client: http::client()?,
+            token: config.discord_token.clone(),
+            settings,
+            database: Mutex::new(config.get_database_handle()?),
+        };
+
+        // Streams in the first load of the index aren't sent as updates, so the ones
+        // scheduled or started while the bot was offline are caught up on here.
+        let streams = loop {
+            {
+                let index = index_receiver.borrow();
+
+                if !index.is_empty() {
+                    break index
+                        .values()
+                        .filter(|s| matches!(s.state, StreamState::Scheduled | StreamState::Live))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                }
+            }
+
+            index_receiver.changed().await.context(here!())?;
+        };
+
+        for stream in &streams {
+            let result = match (&stream.state, events.load(&stream.url).await) {
+                (StreamState::Scheduled, Ok(None)) => events.schedule(stream).await,
+                (StreamState::Live, Ok(Some(_))) => events.start(stream).await,
+                (_, Ok(_)) => continue,
+                (_, Err(e)) => Err(e),
+            };
+
+            if let Err(e) = result {
+                error!("{:?}", e);
+            }
+        }
+
+        while let Some(update) = updates.recv().await {
+            let result = match &update {
+                StreamUpdate::Scheduled(stream) => events.schedule(stream).await,
+                StreamUpdate::Started(stream) => events.start(stream).await,
+                StreamUpdate::Ended(stream) => events.end(stream).await,
+                StreamUpdate::Cancelled(stream) => events.cancel(stream).await,
+            };
+
+            if let Err(e) = result {
+                error!("{:?}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl StreamEvents {
+    /// Creates an event for the stream, or moves its event if the stream was rescheduled.
+    async fn schedule(&self, stream: &Livestream) -> anyhow::Result<()> {
+        // Discord doesn't allow events to be scheduled in the past.
+        if !self.settings.includes(&stream.streamer) || stream.start_at <= Utc::now() {
+            return Ok(());
+        }
+
+        let (start, end) = Self::event_times(stream);
+
+        if let Some((event, _)) = self.load(&stream.url).await? {
+            info!(stream = %stream.title, "Rescheduling stream event.");
+
+            let rescheduled = self
+                .request_existing(
+                    Method::PATCH,
+                    &format!("/{}", event),
+                    Some(json!({
+                        "name": Self::event_name(stream),
+                        "scheduled_start_time": start.to_rfc3339(),
+                        "scheduled_end_time": end.to_rfc3339(),
+                    })),
+                )
+                .await?;
+
+            if rescheduled.is_some() {
+                return Ok(());
+            }
+
+            // The event was deleted or has ended, so the stream gets a new one.
+            self.remove(&stream.url).await?;
+        }
+
+        info!(stream = %stream.title, "Creating stream event.");
+
+        let url = format!("https://youtube.com/watch?v={}", stream.url);
+
+        let mut body = json!({
+            "name": Self::event_name(stream),
+            "description": format!("{} on YouTube: {}", stream.streamer.display_name, url),
+            "privacy_level": PRIVACY_GUILD_ONLY,
+            "entity_type": ENTITY_TYPE_EXTERNAL,
+            "entity_metadata": { "location": url },
+            "scheduled_start_time": start.to_rfc3339(),
+            "scheduled_end_time": end.to_rfc3339(),
+        });
+
+        // The event is still worth creating without a cover image.
+        match self.thumbnail(stream).await {
+            Ok(image) => body["image"] = Value::String(image),
+            Err(e) => warn!(?e, "Couldn't fetch stream thumbnail."),
+        }
+
+        let event: ScheduledEvent = self
+            .request(Method::POST, "", Some(body))
+            .await?
+            .json()
+            .await
+            .context(here!())?;
+
+        self.save(&stream.url, event.id, false).await
+    }
+
+    async fn start(&self, stream: &Livestream) -> anyhow::Result<()> {
+        let event = match self.load(&stream.url).await? {
+            Some((event, false)) => event,
+            _ => return Ok(()),
+        };
+
+        let started = self
+            .request_existing(
+                Method::PATCH,
+                &format!("/{}", event),
+                Some(json!({ "status": STATUS_ACTIVE })),
+            )
+            .await?;
+
+        match started {
+            Some(_) => self.save(&stream.url, event, true).await,
+            None => self.remove(&stream.url).await,
+        }
+    }
+
+    /// Completes the event, or deletes it if the stream was never seen going live.
+    async fn end(&self, stream: &Livestream) -> anyhow::Result<()> {
+        let (event, started) = match self.load(&stream.url).await? {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+
+        // The event is forgotten either way if it's already gone.
+        if started {
+            self.request_existing(
+                Method::PATCH,
+                &format!("/{}", event),
+                Some(json!({ "status": STATUS_COMPLETED })),
+            )
+            .await?;
+        } else {
+            self.request_existing(Method::DELETE, &format!("/{}", event), None)
+                .await?;
+        }
+
+        self.remove(&stream.url).await
+    }
+
+    async fn cancel(&self, stream: &Livestream) -> anyhow::Result<()> {
+        if let Some((event, _)) = self.load(&stream.url).await? {
+            info!(stream = %stream.title, "Deleting event of cancelled stream.");
+
+            self.request_existing(Method::DELETE, &format!("/{}", event), None)
+                .await?;
+
+            self.remove(&stream.url).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<Response> {
+        let mut request = self
+            .client
+            .request(
+                method,
+                format!(
+                    "{}/guilds/{}/scheduled-events{}",
+                    API_BASE, self.settings.guild, path
+                ),
+            )
+            .header("Authorization", format!("Bot {}", self.token));
+
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        http::send_with_retries(request).await
+    }
+
+    /// Sends a request about an existing event, returning `None` if Discord no longer has
+    /// the event or it has already ended, since there's nothing left to change then.
+    async fn request_existing(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<Option<Response>> {
+        match self.request(method, path, body).await {
+            Ok(response) => Ok(Some(response)),
+            Err(e) if Self::is_gone(&e) => {
+                warn!(?e, "Stream event is already gone.");
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn is_gone(error: &anyhow::Error) -> bool {
+        let error = match error.downcast_ref::<http::StatusError>() {
+            Some(e) => e,
+            None => return false,
+        };
+
+        let code = serde_json::from_str::<Value>(&error.body)
+            .ok()
+            .and_then(|b| b.get("code").and_then(Value::as_u64));
+
+        error.status == StatusCode::NOT_FOUND || code == Some(EVENT_FINISHED_CODE)
+    }
+
+    /// Downloads the stream's thumbnail as a data URI, which is how Discord takes images.
+    async fn thumbnail(&self, stream: &Livestream) -> anyhow::Result<String> {
+        let response = http::send_with_retries(self.client.get(&stream.thumbnail)).await?;
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|t| t.to_str().ok())
+            .unwrap_or("image/jpeg")
+            .to_owned();
+
+        let image = response.bytes().await.context(here!())?;
+
+        Ok(format!(
+            "data:{};base64,{}",
+            content_type,
+            base64::encode(&image)
+        ))
+    }
+
+    fn event_name(stream: &Livestream) -> String {
+        stream.title.chars().take(NAME_LIMIT).collect()
+    }
+
+    fn event_times(stream: &Livestream) -> (DateTime<Utc>, DateTime<Utc>) {
+        let length = stream.duration.map_or_else(
+            || Duration::hours(DEFAULT_EVENT_HOURS),
+            |d| Duration::seconds(d.into()),
+        );
+
+        (stream.start_at, stream.start_at + length)
+    }
+
+    /// Returns the stream's event, and whether it has been started.
+    async fn load(&self, video_id: &str) -> anyhow::Result<Option<(u64, bool)>> {
+        let database = self.database.lock().await;
+
+        database
+            .query_row(
+                "SELECT event_id, started FROM StreamEvents WHERE video_id = ? AND guild_id = ?",
+                params![video_id, self.settings.guild],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .context(here!())
+    }
+
+    async fn save(&self, video_id: &str, event: u64, started: bool) -> anyhow::Result<()> {
+        let database = self.database.lock().await;
+
+        database
+            .execute(
+                "INSERT OR REPLACE INTO StreamEvents (video_id, guild_id, event_id, started) VALUES (?, ?, ?, ?)",
+                params![video_id, self.settings.guild, event, started],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Forgets the stream's event, once Discord is done with it.
+    async fn remove(&self, video_id: &str) -> anyhow::Result<()> {
+        let database = self.database.lock().await;
+
+        database
+            .execute(
+                "DELETE FROM StreamEvents WHERE video_id = ? AND guild_id = ?",
+                params![video_id, self.settings.guild],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+}
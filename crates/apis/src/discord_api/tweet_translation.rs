@@ -0,0 +1,71 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Context;
+use serenity::CacheAndHttp;
+use tokio::sync::mpsc;
+use tracing::{instrument, warn};
+
+use utility::{config::Config, here};
+
+use super::{DiscordApi, DiscordMessageData};
+use crate::{translation_api::TranslationApi, twitter_api::HoloTweet};
+
+impl DiscordApi {
+    /// Translates tweets into the languages of the channels they're routed to before handing
+    /// them to the posting thread, so other posts never wait on slow translators. Everything
+    /// else from Twitter is passed along in order.
+    #[instrument(skip(ctx, config, translator, tweets, posting))]
+    pub(super) async fn tweet_translation_thread(
+        ctx: Arc<CacheAndHttp>,
+        config: Config,
+        translator: Arc<TranslationApi>,
+        mut tweets: mpsc::Receiver<DiscordMessageData>,
+        posting: mpsc::Sender<DiscordMessageData>,
+    ) -> anyhow::Result<()> {
+        while let Some(mut message) = tweets.recv().await {
+            if let DiscordMessageData::Tweet(tweet) = &mut message {
+                Self::translate_tweet(&ctx, &config, &translator, tweet).await;
+            }
+
+            posting.send(message).await.context(here!())?;
+        }
+
+        Ok(())
+    }
+
+    async fn translate_tweet(
+        ctx: &Arc<CacheAndHttp>,
+        config: &Config,
+        translator: &TranslationApi,
+        tweet: &mut HoloTweet,
+    ) {
+        let lang = match &tweet.lang {
+            Some(lang) => lang.clone(),
+            None => return,
+        };
+
+        // Each target language is only tried once, even if the translation failed.
+        let mut attempted = HashSet::new();
+
+        for channel in Self::tweet_destinations(config, tweet) {
+            let guild = ctx.cache.guild_channel(channel).await.map(|c| c.guild_id);
+            let policy = config.translation.policy_for(channel, guild);
+
+            if !policy.should_translate(&lang) || !attempted.insert(policy.target.clone()) {
+                continue;
+            }
+
+            match translator
+                .translate(&tweet.plain_text, &lang, &policy.target)
+                .await
+            {
+                Ok(translation) => {
+                    tweet
+                        .translations
+                        .insert(policy.target.clone(), translation);
+                }
+                Err(e) => warn!("{:?}", e),
+            }
+        }
+    }
+}
@@ -24,9 +24,23 @@ use super::discord_api::DiscordMessageData;
 type StreamIndex = Arc<Mutex<HashMap<u32, Livestream>>>;
 type NotifiedStreams = Arc<Mutex<HashSet<String>>>;
 
+/// How often the notifiers check for streams and Spaces, unless one is due to start sooner.
+const NOTIFIER_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct HoloApi;
 
 impl HoloApi {
+    /// Returns how long a notifier should wait before checking again, waking up right when the
+    /// next stream or Space is due to start.
+    #[must_use]
+    pub fn notifier_sleep(next_start: Option<DateTime<Utc>>) -> Duration {
+        next_start
+            .and_then(|start| (start - Utc::now()).to_std().ok())
+            .map_or(NOTIFIER_INTERVAL, |remaining| {
+                remaining.min(NOTIFIER_INTERVAL)
+            })
+    }
+
     #[instrument(skip(config, live_sender, update_sender, exit_receiver))]
     pub async fn start(
         config: Config,
@@ -152,14 +166,34 @@ impl HoloApi {
             let mut new_index = HashMap::with_capacity(stream_index.capacity());
 
             if !stream_index.is_empty() {
-                // Check for newly scheduled streams.
+                // Check for newly scheduled or rescheduled streams.
                 for (id, scheduled_stream) in &scheduled_streams {
-                    if !stream_index.contains_key(id) {
+                    let rescheduled = stream_index
+                        .get(id)
+                        .map_or(true, |s| s.start_at != scheduled_stream.start_at);
+
+                    if rescheduled {
                         stream_updates
                             .send(StreamUpdate::Scheduled(scheduled_stream.clone()))
                             .context(here!())?;
                     }
                 }
+
+                // Streams that disappear well before they were due to start have been
+                // cancelled or made private.
+                for (id, indexed) in stream_index.iter() {
+                    if indexed.state == StreamState::Scheduled
+                        && !scheduled_streams.contains_key(id)
+                        && !live_streams.contains_key(id)
+                        && !ended_streams.contains_key(id)
+                        && (indexed.start_at - Utc::now()).num_minutes() >= 5
+                    {
+                        info!(stream = %indexed.title, "Stream was cancelled!");
+                        stream_updates
+                            .send(StreamUpdate::Cancelled(indexed.clone()))
+                            .context(here!())?;
+                    }
+                }
             }
 
             // Update new index.
@@ -222,7 +256,6 @@ impl HoloApi {
         let mut next_stream_start = Utc::now();
 
         loop {
-            let mut sleep_duration = Duration::from_secs(60);
             let mut stream_index = notifier_lock.lock().await;
             let mut notified = notified_streams.lock().await;
 
@@ -238,7 +271,7 @@ impl HoloApi {
             if sorted_streams.is_empty() {
                 std::mem::drop(notified);
                 std::mem::drop(stream_index);
-                sleep(sleep_duration).await;
+                sleep(Self::notifier_sleep(None)).await;
                 continue;
             }
 
@@ -264,13 +297,7 @@ impl HoloApi {
                 std::mem::drop(notified);
                 std::mem::drop(stream_index);
 
-                let remaining_time_std = remaining_time.to_std().context(here!())?;
-
-                if remaining_time_std <= sleep_duration {
-                    sleep_duration = remaining_time_std;
-                }
-
-                sleep(sleep_duration).await;
+                sleep(Self::notifier_sleep(Some(start_at))).await;
                 continue;
             }
 
@@ -317,7 +344,7 @@ impl HoloApi {
 
             std::mem::drop(stream_index);
 
-            sleep(sleep_duration).await;
+            sleep(NOTIFIER_INTERVAL).await;
         }
     }
 
@@ -402,9 +429,12 @@ pub enum StreamState {
 
 #[derive(Debug, Clone)]
 pub enum StreamUpdate {
+    /// A stream was scheduled, or its start time changed.
     Scheduled(Livestream),
     Started(Livestream),
     Ended(Livestream),
+    /// A scheduled stream disappeared before it started.
+    Cancelled(Livestream),
 }
 
 #[derive(Deserialize, Debug)]
@@ -0,0 +1,103 @@
+use std::{fmt, time::Duration};
+
+use anyhow::{anyhow, Context};
+use backoff::ExponentialBackoff;
+use once_cell::sync::OnceCell;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use tracing::warn;
+
+use utility::here;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_RETRY_TIME: Duration = Duration::from_secs(30);
+
+/// HTTP client shared by the APIs that authenticate per request.
+pub(crate) fn client() -> anyhow::Result<Client> {
+    static CLIENT: OnceCell<Client> = OnceCell::new();
+
+    CLIENT
+        .get_or_try_init(|| {
+            reqwest::ClientBuilder::new()
+                .user_agent(concat!(
+                    env!("CARGO_PKG_NAME"),
+                    "/",
+                    env!("CARGO_PKG_VERSION"),
+                ))
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+        })
+        .map(Client::clone)
+        .context(here!())
+}
+
+/// Response of a request that failed in a way retrying won't fix, such as a client error.
+#[derive(Debug)]
+pub(crate) struct StatusError {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Request failed with status {}: {}",
+            self.status, self.body
+        )
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// Sends the request, retrying with exponential backoff on connection errors, rate limits
+/// and server errors. Timeouts are only retried for idempotent methods, since the server
+/// may have handled a POST that timed out on our end. Other failed responses are returned
+/// as a [`StatusError`].
+pub(crate) async fn send_with_retries(request: RequestBuilder) -> anyhow::Result<Response> {
+    let idempotent = request
+        .try_clone()
+        .and_then(|r| r.build().ok())
+        .map_or(false, |r| r.method().is_idempotent());
+
+    let backoff_config = ExponentialBackoff {
+        initial_interval: Duration::from_millis(500),
+        max_elapsed_time: Some(MAX_RETRY_TIME),
+        ..ExponentialBackoff::default()
+    };
+
+    backoff::future::retry(backoff_config, || async {
+        let request = request
+            .try_clone()
+            .ok_or_else(|| backoff::Error::Permanent(anyhow!("Request can't be retried.")))?;
+
+        let response = request.send().await.map_err(|e| {
+            warn!("{}", e);
+
+            if e.is_connect() || (idempotent && (e.is_timeout() || e.is_request())) {
+                backoff::Error::Transient(anyhow!(e).context(here!()))
+            } else {
+                backoff::Error::Permanent(anyhow!(e).context(here!()))
+            }
+        })?;
+
+        match response.status() {
+            s if s.is_success() => Ok(response),
+            s if s == StatusCode::TOO_MANY_REQUESTS || s.is_server_error() => {
+                warn!(status = %s, "Request failed, retrying.");
+                Err(backoff::Error::Transient(
+                    anyhow!("Request failed with status {}.", s).context(here!()),
+                ))
+            }
+            status => {
+                let body = response.text().await.unwrap_or_default();
+
+                Err(backoff::Error::Permanent(
+                    anyhow!(StatusError { status, body }).context(here!()),
+                ))
+            }
+        }
+    })
+    .await
+}
@@ -1,6 +1,7 @@
 pub mod birthday_reminder;
 pub mod discord_api;
 pub mod holo_api;
+mod http;
 pub mod meme_api;
 pub mod openai_api;
 pub mod reminder_notifier;
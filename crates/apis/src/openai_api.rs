@@ -1,11 +1,13 @@
-use core::f64;
-
-use anyhow::anyhow;
-use openai_api::{api, Client};
+use anyhow::{anyhow, Context};
+use reqwest::{header, Client};
+use serde::Deserialize;
+use serde_json::json;
 use tracing::instrument;
 
 use tokio::sync::RwLock;
-use utility::config::Config;
+use utility::{config::Config, here};
+
+use crate::http;
 
 const START_SEQUENCE: &str = "\nPekora:";
 const RESTART_SEQUENCE: &str = "\n\nFan:";
@@ -27,18 +29,27 @@ Pekora: Eating carrots peko.
 Fan: What is your favorite game?
 Pekora: Minecraft peko. I like blowing up things peko.";
 
-#[derive(Debug)]
+const COMPLETIONS_URL: &str = "https://api.openai.com/v1/engines/davinci/completions";
+/// Completions often take longer than the shared client's timeout allows.
+const COMPLETION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
 pub struct OpenAiApi {
     client: Client,
+    token: String,
     last_message: RwLock<String>,
 }
 
+impl std::fmt::Debug for OpenAiApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenAiApi").finish()
+    }
+}
+
 impl OpenAiApi {
     pub fn new(config: &Config) -> anyhow::Result<Self> {
-        let client = Client::new(&config.openai_token);
-
         Ok(Self {
-            client,
+            client: http::client()?,
+            token: config.openai_token.clone(),
             last_message: RwLock::new(INIT_PROMPT.to_string()),
         })
     }
@@ -48,9 +59,9 @@ impl OpenAiApi {
         *self.last_message.write().await = INIT_PROMPT.to_string()
     }
 
-    #[instrument]
+    #[instrument(skip(self))]
     pub async fn prompt(
-        &'static self,
+        &self,
         prompt: &str,
         temperature: f64,
         top_p: f64,
@@ -64,23 +75,26 @@ impl OpenAiApi {
             last_msg, RESTART_SEQUENCE, prompt, START_SEQUENCE
         );
 
-        let args = api::CompletionArgs::builder()
-            .prompt(&full_prompt)
-            .engine(api::Engine::Davinci)
-            .max_tokens(64)
-            .temperature(temperature)
-            .top_p(top_p)
-            .presence_penalty(presence_penalty)
-            .frequency_penalty(frequency_penalty)
-            .stop(vec!["\n".into()])
-            .build()
-            .map_err(|e| anyhow!(e))?;
-
-        let completion =
-            tokio::task::spawn_blocking(move || -> Result<api::Completion, openai_api::Error> {
-                self.client.complete_prompt_sync(args)
-            })
-            .await??;
+        let body = json!({
+            "prompt": full_prompt,
+            "max_tokens": 64,
+            "temperature": temperature,
+            "top_p": top_p,
+            "presence_penalty": presence_penalty,
+            "frequency_penalty": frequency_penalty,
+            "stop": ["\n"],
+        });
+
+        let response = http::send_with_retries(
+            self.client
+                .post(COMPLETIONS_URL)
+                .header(header::AUTHORIZATION, format!("Bearer {}", self.token))
+                .timeout(COMPLETION_TIMEOUT)
+                .json(&body),
+        )
+        .await?;
+
+        let completion: Completion = response.json().await.context(here!())?;
 
         let choice = completion
             .choices
@@ -93,3 +107,13 @@ impl OpenAiApi {
         Ok(answer)
     }
 }
+
+#[derive(Debug, Deserialize)]
+struct Completion {
+    choices: Vec<CompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionChoice {
+    text: String,
+}
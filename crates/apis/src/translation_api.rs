@@ -1,28 +1,50 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Context};
 use async_trait::async_trait;
-use deepl_api::{DeepL, TranslatableTextList};
+use chrono::{DateTime, Utc};
+use futures::future;
 use libretranslate::{translate, Language};
 use reqwest::{header, Client};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::Deserialize;
 use serde_json::json;
+use serenity::prelude::TypeMapKey;
 use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
-use tracing::{info, instrument};
+use tracing::{debug, info, instrument, warn};
 
-use utility::{config::Config, here};
+use utility::{
+    config::{normalise_lang, Config, TranslationConfig},
+    here,
+};
 
-#[allow(dead_code)]
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, EnumIter)]
-pub enum TranslatorType {
-    Azure,
-    DeepL,
-    Libre,
-}
+use crate::http;
+
+pub use utility::config::TranslatorType;
+
+pub use glossary::Glossary;
+pub use language_detection::detect_language;
+
+pub mod glossary;
+pub mod language_detection;
+
+/// Prefix of the custom ID of the button that compares the translations of a tweet.
+pub const COMPARE_TWEET_BUTTON: &str = "translate_compare";
+/// Prefix of the custom ID of the buttons used to vote for the best translation.
+pub const TRANSLATION_VOTE_BUTTON: &str = "translate_vote";
 
 pub struct TranslationApi {
     translators: HashMap<TranslatorType, Box<dyn Translator + 'static>>,
+    health: HashMap<TranslatorType, Mutex<TranslatorHealth>>,
+    config: TranslationConfig,
+    database: Mutex<Connection>,
 }
 
 impl std::fmt::Debug for TranslationApi {
@@ -39,18 +61,38 @@ impl std::fmt::Debug for TranslationApi {
 }
 
 impl TranslationApi {
+    /// Number of votes for a language before they're used to reorder its fallback chain.
+    const MIN_ROUTING_VOTES: u64 = 10;
+
     pub fn new(config: &Config) -> anyhow::Result<Self> {
         let mut translators: HashMap<TranslatorType, Box<dyn Translator + 'static>> =
             HashMap::new();
 
+        let cache = Arc::new(TranslationCache::new(config)?);
+        let glossary = Arc::new(Glossary::new(config)?);
+
         for translator in TranslatorType::iter() {
+            let inner: Box<dyn Translator + 'static> = match translator {
+                TranslatorType::Azure => Box::new(AzureApi { client: None }),
+                TranslatorType::DeepL => Box::new(DeepLApi {
+                    client: None,
+                    auth_key: String::new(),
+                    usage: Mutex::new(None),
+                    quota_threshold: config.translation.deepl_quota_threshold,
+                }),
+                TranslatorType::Libre => Box::new(LibreApi {}),
+            };
+
             translators.insert(
                 translator,
-                match translator {
-                    TranslatorType::Azure => Box::new(AzureApi { client: None }),
-                    TranslatorType::DeepL => Box::new(DeepLApi { client: None }),
-                    TranslatorType::Libre => Box::new(LibreApi {}),
-                },
+                Box::new(CachedTranslator {
+                    kind: translator,
+                    inner: Box::new(GlossaryTranslator {
+                        inner,
+                        glossary: Arc::clone(&glossary),
+                    }),
+                    cache: Arc::clone(&cache),
+                }),
             );
 
             translators
@@ -60,25 +102,489 @@ impl TranslationApi {
                 .initialize(config)?;
         }
 
-        Ok(Self { translators })
+        let health = TranslatorType::iter()
+            .map(|t| (t, Mutex::new(TranslatorHealth::default())))
+            .collect();
+
+        Ok(Self {
+            translators,
+            health,
+            config: config.translation.clone(),
+            database: Mutex::new(config.get_database_handle()?),
+        })
     }
 
-    #[must_use]
-    #[allow(clippy::indexing_slicing)]
-    pub fn get_translator_for_lang(&self, lang: &str) -> &(dyn Translator + 'static) {
-        let best_api = match lang {
-            "ja" | "jp" | "de" => TranslatorType::Libre,
-            _ => TranslatorType::Azure,
+    /// Translates the text with the first healthy translator in the language's
+    /// fallback chain, moving on to the next one if it fails. Translators users voted
+    /// for the most are tried first.
+    #[instrument(skip(self, text))]
+    pub async fn translate(&self, text: &str, lang: &str, to: &str) -> anyhow::Result<String> {
+        let lang = Self::resolve_lang(text, lang)?;
+
+        if normalise_lang(lang) == normalise_lang(to) {
+            return Ok(text.to_owned());
+        }
+
+        let cooldown = Duration::from_secs(self.config.cooldown);
+
+        for translator_type in &self.ranked_chain(lang) {
+            let translator = match self.translators.get(translator_type) {
+                Some(t) => t,
+                None => continue,
+            };
+
+            if !self.is_healthy(*translator_type) || !translator.is_available() {
+                debug!(?translator_type, "Skipping unavailable translator.");
+                continue;
+            }
+
+            match translator.translate(text, lang, to).await {
+                Ok(tl) => {
+                    self.update_health(*translator_type, |h| h.record_success());
+                    return Ok(tl);
+                }
+                Err(e) => {
+                    warn!(?translator_type, "Translation failed: {:?}", e);
+
+                    let threshold = self.config.failure_threshold;
+                    self.update_health(*translator_type, |h| {
+                        h.record_failure(threshold, cooldown);
+                    });
+                }
+            }
+        }
+
+        Err(anyhow!("No translator could translate from '{}'.", lang).context(here!()))
+    }
+
+    /// Translates the text with a specific translator, bypassing the fallback chain.
+    #[instrument(skip(self, text))]
+    pub async fn translate_with(
+        &self,
+        translator_type: TranslatorType,
+        text: &str,
+        lang: &str,
+        to: &str,
+    ) -> anyhow::Result<String> {
+        let lang = Self::resolve_lang(text, lang)?;
+
+        if normalise_lang(lang) == normalise_lang(to) {
+            return Ok(text.to_owned());
+        }
+
+        let translator = self
+            .translators
+            .get(&translator_type)
+            .ok_or_else(|| anyhow!("Translator {:?} not found.", translator_type))
+            .context(here!())?;
+
+        if !translator.is_available() {
+            return Err(
+                anyhow!("Translator {:?} is currently unavailable.", translator_type)
+                    .context(here!()),
+            );
+        }
+
+        translator.translate(text, lang, to).await
+    }
+
+    /// Translates the text with every translator at once, so the results can be compared.
+    #[instrument(skip(self, text))]
+    pub async fn compare(
+        &self,
+        text: &str,
+        lang: &str,
+        to: &str,
+    ) -> anyhow::Result<Vec<(TranslatorType, anyhow::Result<String>)>> {
+        let lang = Self::resolve_lang(text, lang)?;
+
+        let results = future::join_all(TranslatorType::iter().filter_map(|translator_type| {
+            let translator = self.translators.get(&translator_type)?;
+
+            Some(async move {
+                let result = if translator.is_available() {
+                    translator.translate(text, lang, to).await
+                } else {
+                    Err(anyhow!("Translator is currently unavailable.").context(here!()))
+                };
+
+                (translator_type, result)
+            })
+        }))
+        .await;
+
+        Ok(results)
+    }
+
+    /// Records which translation a user picked as the best one.
+    /// Each user gets one vote per compared text, later votes replace earlier ones.
+    pub fn record_vote(
+        &self,
+        source_id: &str,
+        user: u64,
+        lang: &str,
+        translator: TranslatorType,
+    ) -> anyhow::Result<()> {
+        let handle = self
+            .database
+            .lock()
+            .map_err(|e| anyhow!("{}", e))
+            .context(here!())?;
+
+        handle
+            .execute(
+                "INSERT OR REPLACE INTO TranslationVotes (source_id, user_id, source_lang, translator, voted_at)
+                    VALUES (?, ?, ?, ?, ?)",
+                params![
+                    source_id,
+                    user,
+                    normalise_lang(lang),
+                    translator.to_string(),
+                    Utc::now()
+                ],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Number of times each translator was picked as the best one for a language.
+    pub fn vote_counts(&self, lang: &str) -> anyhow::Result<HashMap<TranslatorType, u64>> {
+        let handle = self
+            .database
+            .lock()
+            .map_err(|e| anyhow!("{}", e))
+            .context(here!())?;
+
+        let mut stmt = handle
+            .prepare(
+                "SELECT translator, COUNT(*) FROM TranslationVotes WHERE source_lang = ? GROUP BY translator",
+            )
+            .context(here!())?;
+
+        let counts = stmt
+            .query_map([normalise_lang(lang)], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?))
+            })
+            .context(here!())?
+            .filter_map(|row| {
+                let (translator, count) = row.ok()?;
+                Some((translator.parse().ok()?, count))
+            })
+            .collect();
+
+        Ok(counts)
+    }
+
+    /// Returns the language's fallback chain, ordered by the votes each translator got.
+    /// The configured order is kept until there are enough votes, and for ties.
+    fn ranked_chain(&self, lang: &str) -> Vec<TranslatorType> {
+        let mut chain = self.config.chain_for_lang(lang).to_vec();
+
+        let votes = match self.vote_counts(lang) {
+            Ok(votes) => votes,
+            Err(e) => {
+                warn!("{:?}", e);
+                return chain;
+            }
         };
 
-        self.translators[&best_api].as_ref()
+        if votes.values().sum::<u64>() >= Self::MIN_ROUTING_VOTES {
+            chain.sort_by_key(|t| std::cmp::Reverse(votes.get(t).copied().unwrap_or(0)));
+        }
+
+        chain
     }
+
+    /// Detects the language of the text if it wasn't tagged with one.
+    fn resolve_lang<'a>(text: &str, lang: &'a str) -> anyhow::Result<&'a str> {
+        match lang {
+            "" | "und" => detect_language(text)
+                .ok_or_else(|| anyhow!("Couldn't detect the language of the text."))
+                .context(here!()),
+            _ => Ok(lang),
+        }
+    }
+
+    fn is_healthy(&self, translator: TranslatorType) -> bool {
+        self.health
+            .get(&translator)
+            .and_then(|h| h.lock().ok())
+            .map_or(true, |h| h.is_healthy())
+    }
+
+    fn update_health<F: FnOnce(&mut TranslatorHealth)>(&self, translator: TranslatorType, f: F) {
+        if let Some(mut health) = self.health.get(&translator).and_then(|h| h.lock().ok()) {
+            f(&mut health);
+        }
+    }
+}
+
+impl TypeMapKey for TranslationApi {
+    type Value = Arc<Self>;
 }
 
 #[async_trait]
 pub trait Translator: Send + Sync {
     fn initialize(&mut self, config: &Config) -> anyhow::Result<()>;
-    async fn translate(&self, text: &str, from: &str) -> anyhow::Result<String>;
+    async fn translate(&self, text: &str, from: &str, to: &str) -> anyhow::Result<String>;
+
+    /// Whether the translator can currently accept requests, e.g. hasn't run out of quota.
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// Circuit breaker state for a single translator.
+#[derive(Debug, Default)]
+struct TranslatorHealth {
+    consecutive_failures: u32,
+    disabled_until: Option<Instant>,
+}
+
+impl TranslatorHealth {
+    fn is_healthy(&self) -> bool {
+        self.disabled_until
+            .map_or(true, |until| Instant::now() >= until)
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.disabled_until = None;
+    }
+
+    fn record_failure(&mut self, threshold: u32, cooldown: Duration) {
+        self.consecutive_failures += 1;
+
+        if self.consecutive_failures >= threshold {
+            warn!(
+                "Translator failed {} times in a row, disabling it for {}s.",
+                self.consecutive_failures,
+                cooldown.as_secs()
+            );
+
+            self.disabled_until = Some(Instant::now() + cooldown);
+            self.consecutive_failures = 0;
+        }
+    }
+}
+
+/// Persistent cache of translations, shared between all translators.
+pub struct TranslationCache {
+    handle: Mutex<Connection>,
+    ttl: chrono::Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TranslationCache {
+    const STATS_LOG_INTERVAL: u64 = 50;
+
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let handle = config.get_database_handle()?;
+        let ttl = chrono::Duration::days(config.translation.cache_ttl_days);
+
+        let removed = handle
+            .execute(
+                "DELETE FROM TranslationCache WHERE created_at < ?",
+                [Utc::now() - ttl],
+            )
+            .context(here!())?;
+
+        debug!(removed, "Purged expired translations from cache.");
+
+        Ok(Self {
+            handle: Mutex::new(handle),
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    pub fn get(
+        &self,
+        text: &str,
+        from: &str,
+        to: &str,
+        translator: TranslatorType,
+    ) -> anyhow::Result<Option<String>> {
+        let handle = self
+            .handle
+            .lock()
+            .map_err(|e| anyhow!("{}", e))
+            .context(here!())?;
+
+        let translation = handle
+            .query_row(
+                "SELECT translation, created_at FROM TranslationCache
+                    WHERE text_hash = ? AND source_text = ? AND source_lang = ? AND target_lang = ? AND translator = ?",
+                params![
+                    Self::hash(text),
+                    text,
+                    normalise_lang(from),
+                    normalise_lang(to),
+                    translator.to_string()
+                ],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, DateTime<Utc>>(1)?)),
+            )
+            .optional()
+            .context(here!())?
+            .and_then(|(tl, created_at)| (Utc::now() - created_at < self.ttl).then(|| tl));
+
+        std::mem::drop(handle);
+
+        let counter = if translation.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        self.log_stats();
+        Ok(translation)
+    }
+
+    pub fn insert(
+        &self,
+        text: &str,
+        from: &str,
+        to: &str,
+        translator: TranslatorType,
+        translation: &str,
+    ) -> anyhow::Result<()> {
+        let handle = self
+            .handle
+            .lock()
+            .map_err(|e| anyhow!("{}", e))
+            .context(here!())?;
+
+        handle
+            .execute(
+                "INSERT OR REPLACE INTO TranslationCache (text_hash, source_text, source_lang, target_lang, translator, translation, created_at)
+                    VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    Self::hash(text),
+                    text,
+                    normalise_lang(from),
+                    normalise_lang(to),
+                    translator.to_string(),
+                    translation,
+                    Utc::now()
+                ],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn hit_rate(&self) -> f32 {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let total = hits + self.misses.load(Ordering::Relaxed);
+
+        if total == 0 {
+            0.0
+        } else {
+            hits as f32 / total as f32
+        }
+    }
+
+    fn log_stats(&self) {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let total = hits + self.misses.load(Ordering::Relaxed);
+
+        if total % Self::STATS_LOG_INTERVAL == 0 {
+            info!(
+                "[CACHE] {} of {} translations ({:.1}%) served from cache.",
+                hits,
+                total,
+                self.hit_rate() * 100.0
+            );
+        }
+    }
+
+    /// 64-bit FNV-1a, used since the hash has to stay stable between builds. The hash only
+    /// narrows the lookup down, the source text is part of the key so collisions can't
+    /// replace each other's entries.
+    #[allow(clippy::cast_possible_wrap)]
+    fn hash(text: &str) -> i64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+
+        text.bytes().fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        }) as i64
+    }
+}
+
+impl std::fmt::Debug for TranslationCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TranslationCache")
+            .field("ttl", &self.ttl)
+            .field("hit_rate", &self.hit_rate())
+            .finish()
+    }
+}
+
+/// Wraps a translator, serving repeated translations from the cache.
+struct CachedTranslator {
+    kind: TranslatorType,
+    inner: Box<dyn Translator + 'static>,
+    cache: Arc<TranslationCache>,
+}
+
+#[async_trait]
+impl Translator for CachedTranslator {
+    fn initialize(&mut self, config: &Config) -> anyhow::Result<()> {
+        self.inner.initialize(config)
+    }
+
+    fn is_available(&self) -> bool {
+        self.inner.is_available()
+    }
+
+    #[instrument(skip(self, text))]
+    async fn translate(&self, text: &str, from: &str, to: &str) -> anyhow::Result<String> {
+        match self.cache.get(text, from, to, self.kind) {
+            Ok(Some(translation)) => return Ok(translation),
+            Ok(None) => (),
+            Err(e) => debug!("{:?}", e),
+        }
+
+        let translation = self.inner.translate(text, from, to).await?;
+
+        if let Err(e) = self.cache.insert(text, from, to, self.kind, &translation) {
+            debug!("{:?}", e);
+        }
+
+        Ok(translation)
+    }
+}
+
+/// Wraps a translator, keeping glossary terms out of its reach.
+struct GlossaryTranslator {
+    inner: Box<dyn Translator + 'static>,
+    glossary: Arc<Glossary>,
+}
+
+#[async_trait]
+impl Translator for GlossaryTranslator {
+    fn initialize(&mut self, config: &Config) -> anyhow::Result<()> {
+        self.inner.initialize(config)
+    }
+
+    fn is_available(&self) -> bool {
+        self.inner.is_available()
+    }
+
+    #[instrument(skip(self, text))]
+    async fn translate(&self, text: &str, from: &str, to: &str) -> anyhow::Result<String> {
+        let (protected, substitutions) = self.glossary.protect(text);
+        let translation = self.inner.translate(&protected, from, to).await?;
+
+        Ok(Glossary::restore(&translation, &substitutions))
+    }
 }
 
 #[derive(Debug)]
@@ -113,21 +619,20 @@ impl Translator for AzureApi {
     }
 
     #[instrument]
-    async fn translate(&self, text: &str, from: &str) -> anyhow::Result<String> {
+    async fn translate(&self, text: &str, from: &str, to: &str) -> anyhow::Result<String> {
         let data = json!([{ "Text": &text }]);
         let src_lang = match from {
-            "jp" => "ja",
-            "in" => "id",
             "und" => {
                 return Err(anyhow!("[AZURE] Invalid source language.").context(here!()));
             }
-            _ => from,
+            _ => normalise_lang(from),
         };
+        let dst_lang = normalise_lang(to);
 
         if let Some(client) = &self.client {
             let response = client
                 .post("https://api.cognitive.microsofttranslator.com/translate")
-                .query(&[("api-version", "3.0"), ("to", "en"), ("from", src_lang)])
+                .query(&[("api-version", "3.0"), ("to", dst_lang), ("from", src_lang)])
                 .header(header::CONTENT_TYPE, "application/json; charset=UTF-8")
                 .header(header::CONTENT_LENGTH, data.to_string().len())
                 .json(&data)
@@ -163,20 +668,104 @@ impl Translator for AzureApi {
 }
 
 struct DeepLApi {
-    client: Option<DeepL>,
+    client: Option<Client>,
+    auth_key: String,
+    usage: Mutex<Option<DeepLUsage>>,
+    quota_threshold: f32,
+}
+
+/// Characters used and the character limit, as of the last usage check.
+#[derive(Debug, Clone, Copy)]
+struct DeepLUsage {
+    count: u64,
+    limit: u64,
+    checked_at: Instant,
+}
+
+impl DeepLApi {
+    const USAGE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+    /// Free API keys have to use a different endpoint.
+    fn endpoint(&self, path: &str) -> String {
+        let host = if self.auth_key.ends_with(":fx") {
+            "https://api-free.deepl.com"
+        } else {
+            "https://api.deepl.com"
+        };
+
+        format!("{}/v2/{}", host, path)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    async fn update_usage(&self, client: &Client) -> anyhow::Result<()> {
+        let is_stale = self
+            .usage
+            .lock()
+            .map_err(|e| anyhow!("{}", e))
+            .context(here!())?
+            .map_or(true, |u| {
+                u.checked_at.elapsed() >= Self::USAGE_CHECK_INTERVAL
+            });
+
+        if !is_stale {
+            return Ok(());
+        }
+
+        let response = http::send_with_retries(
+            client
+                .get(&self.endpoint("usage"))
+                .header(header::AUTHORIZATION, self.auth_header()),
+        )
+        .await?;
+
+        let usage: DeepLUsageResponse = response.json().await.context(here!())?;
+
+        info!(
+            "[DEEPL] Translated {} of {} ({:.1}%) characters this month.",
+            usage.character_count,
+            usage.character_limit,
+            (usage.character_count as f32 / usage.character_limit as f32) * 100.0
+        );
+
+        *self
+            .usage
+            .lock()
+            .map_err(|e| anyhow!("{}", e))
+            .context(here!())? = Some(DeepLUsage {
+            count: usage.character_count,
+            limit: usage.character_limit,
+            checked_at: Instant::now(),
+        });
+
+        Ok(())
+    }
+
+    fn auth_header(&self) -> String {
+        format!("DeepL-Auth-Key {}", self.auth_key)
+    }
 }
 
 #[async_trait]
 impl Translator for DeepLApi {
     fn initialize(&mut self, config: &Config) -> anyhow::Result<()> {
-        self.client = Some(DeepL::new(config.deepl_key.clone()));
+        self.client = Some(http::client()?);
+        self.auth_key = config.deepl_key.clone();
 
         Ok(())
     }
 
     #[allow(clippy::cast_precision_loss)]
+    fn is_available(&self) -> bool {
+        match self.usage.lock().ok().and_then(|u| *u) {
+            Some(usage) if usage.limit > 0 => {
+                (usage.count as f32 / usage.limit as f32) < self.quota_threshold
+            }
+            _ => true,
+        }
+    }
+
     #[instrument(skip(self))]
-    async fn translate(&self, text: &str, from: &str) -> anyhow::Result<String> {
+    async fn translate(&self, text: &str, from: &str, to: &str) -> anyhow::Result<String> {
         if let Some(client) = &self.client {
             let src_lang = match from {
                 "ja" | "jp" => "JA",
@@ -184,30 +773,39 @@ impl Translator for DeepLApi {
                 _ => return Err(anyhow!("[DEEPL] Invalid source language.").context(here!())),
             };
 
-            let text_list = TranslatableTextList {
-                source_language: Some(src_lang.to_owned()),
-                target_language: "EN-US".to_owned(),
-                texts: vec![text.to_owned()],
+            // DeepL requires a regional variant for some target languages.
+            let dst_lang = match normalise_lang(to) {
+                "en" => "EN-US".to_owned(),
+                "pt" => "PT-BR".to_owned(),
+                lang => lang.to_uppercase(),
             };
 
-            let result = client
-                .translate(None, text_list)
-                .map_err(|e| anyhow!("{}", e))
-                .context(here!())?;
+            if let Err(e) = self.update_usage(client).await {
+                warn!("[DEEPL] Failed to check usage: {:?}", e);
+            }
 
-            let usage = client
-                .usage_information()
-                .map_err(|e| anyhow!("{}", e))
-                .context(here!())?;
+            let response = http::send_with_retries(
+                client
+                    .post(&self.endpoint("translate"))
+                    .header(header::AUTHORIZATION, self.auth_header())
+                    .form(&[
+                        ("text", text),
+                        ("source_lang", src_lang),
+                        ("target_lang", dst_lang.as_str()),
+                    ]),
+            )
+            .await?;
 
-            info!(
-                "[DEEPL] Translated {} of {} ({:.1}%) characters this month.",
-                usage.character_count,
-                usage.character_limit,
-                (usage.character_count as f32 / usage.character_limit as f32) * 100.0
-            );
+            let result: DeepLResponse = response.json().await.context(here!())?;
+
+            // Keep the usage roughly up to date between checks.
+            if let Ok(mut usage) = self.usage.lock() {
+                if let Some(usage) = usage.as_mut() {
+                    usage.count += text.chars().count() as u64;
+                }
+            }
 
-            match &result[..] {
+            match &result.translations[..] {
                 [tl, ..] => Ok(tl.text.clone()),
                 [] => Err(anyhow!("[DEEPL] Translated text wasn't found.").context(here!())),
             }
@@ -220,6 +818,22 @@ impl Translator for DeepLApi {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepLUsageResponse {
+    character_count: u64,
+    character_limit: u64,
+}
+
 #[derive(Debug)]
 struct LibreApi;
 
@@ -230,9 +844,10 @@ impl Translator for LibreApi {
     }
 
     #[instrument]
-    async fn translate(&self, text: &str, from: &str) -> anyhow::Result<String> {
-        let src_lang = from.parse::<Language>()?;
-        let data = translate(src_lang, Language::English, text, None).await?;
+    async fn translate(&self, text: &str, from: &str, to: &str) -> anyhow::Result<String> {
+        let src_lang = normalise_lang(from).parse::<Language>()?;
+        let dst_lang = normalise_lang(to).parse::<Language>()?;
+        let data = translate(src_lang, dst_lang, text, None).await?;
 
         Ok(data.output)
     }
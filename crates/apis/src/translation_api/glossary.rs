@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use regex::{Regex, RegexBuilder};
+
+use utility::{
+    config::{Config, GlossaryTerm, LoadFromDatabase},
+    here, regex,
+};
+
+/// Terms that machine translators shouldn't touch, such as talent names and fandom terms,
+/// mapped to the English form they should always end up as.
+#[derive(Debug)]
+pub struct Glossary {
+    terms: HashMap<String, String>,
+    matcher: Option<Regex>,
+}
+
+impl Glossary {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let handle = config.get_database_handle()?;
+        let curated = GlossaryTerm::load_from_database(&handle)?;
+
+        let roster = config.users.iter().flat_map(|u| {
+            std::iter::once((u.name.clone(), u.name.clone()))
+                .chain(std::iter::once((
+                    u.display_name.clone(),
+                    u.display_name.clone(),
+                )))
+                .chain(
+                    u.aliases
+                        .iter()
+                        .map(move |a| (a.clone(), u.display_name.clone())),
+                )
+        });
+
+        // Curated terms take precedence over the ones generated from the roster.
+        let terms = roster
+            .chain(curated.into_iter().map(|t| (t.term, t.translation)))
+            .filter(|(term, _)| !term.trim().is_empty())
+            .collect::<Vec<_>>();
+
+        Self::from_terms(terms)
+    }
+
+    fn from_terms(terms: Vec<(String, String)>) -> anyhow::Result<Self> {
+        let mut sources = terms.iter().map(|(t, _)| t.clone()).collect::<Vec<_>>();
+
+        // Match the longest terms first, so "Usada Pekora" wins over "Pekora".
+        sources.sort_unstable_by(|a, b| {
+            b.chars()
+                .count()
+                .cmp(&a.chars().count())
+                .then_with(|| a.cmp(b))
+        });
+        sources.dedup();
+
+        let matcher = if sources.is_empty() {
+            None
+        } else {
+            let pattern = sources
+                .iter()
+                .map(|term| {
+                    let escaped = regex::escape(term);
+
+                    // Scripts without spaces between words can't rely on word boundaries.
+                    if term.is_ascii() {
+                        format!(r"\b{}\b", escaped)
+                    } else {
+                        escaped
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("|");
+
+            Some(
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .context(here!())?,
+            )
+        };
+
+        let terms = terms
+            .into_iter()
+            .map(|(term, translation)| (term.to_lowercase(), translation))
+            .collect();
+
+        Ok(Self { terms, matcher })
+    }
+
+    /// Replaces glossary terms with placeholders the translators leave alone.
+    /// Returns the protected text and the English forms of the replaced terms.
+    #[must_use]
+    pub fn protect(&self, text: &str) -> (String, Vec<String>) {
+        let matcher = match &self.matcher {
+            Some(m) => m,
+            None => return (text.to_owned(), Vec::new()),
+        };
+
+        let mut substitutions = Vec::new();
+
+        let protected = matcher.replace_all(text, |caps: &regex::Captures| {
+            let translation = self
+                .terms
+                .get(&caps[0].to_lowercase())
+                .cloned()
+                .unwrap_or_else(|| caps[0].to_owned());
+
+            let index = match substitutions.iter().position(|s| *s == translation) {
+                Some(i) => i,
+                None => {
+                    substitutions.push(translation);
+                    substitutions.len() - 1
+                }
+            };
+
+            format!("[#{}]", index)
+        });
+
+        (protected.into_owned(), substitutions)
+    }
+
+    /// Puts the English forms of protected terms back into the translated text.
+    #[must_use]
+    pub fn restore(text: &str, substitutions: &[String]) -> String {
+        if substitutions.is_empty() {
+            return text.to_owned();
+        }
+
+        // Translators sometimes add spaces inside the placeholders.
+        let placeholder_rgx: &'static Regex = regex!(r"\[\s*#\s*(\d+)\s*\]");
+
+        placeholder_rgx
+            .replace_all(text, |caps: &regex::Captures| {
+                caps[1]
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| substitutions.get(i))
+                    .cloned()
+                    .unwrap_or_else(|| caps[0].to_owned())
+            })
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glossary(terms: &[(&str, &str)]) -> Glossary {
+        Glossary::from_terms(
+            terms
+                .iter()
+                .map(|(term, translation)| ((*term).to_owned(), (*translation).to_owned()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn protects_and_restores_terms() {
+        let glossary = glossary(&[("ぺこら", "Pekora"), ("Usada Pekora", "Usada Pekora")]);
+
+        let (protected, substitutions) = glossary.protect("ぺこらの配信！usada pekora!");
+
+        assert_eq!(protected, "[#0]の配信！[#1]!");
+        assert_eq!(substitutions, vec!["Pekora", "Usada Pekora"]);
+        assert_eq!(
+            Glossary::restore("[#0]'s stream! [#1]!", &substitutions),
+            "Pekora's stream! Usada Pekora!"
+        );
+    }
+
+    #[test]
+    fn longest_term_wins() {
+        let glossary = glossary(&[("Pekora", "Pekora"), ("Usada Pekora", "Usada Pekora")]);
+
+        let (protected, substitutions) = glossary.protect("Usada Pekora and Pekora");
+
+        assert_eq!(protected, "[#0] and [#1]");
+        assert_eq!(substitutions, vec!["Usada Pekora", "Pekora"]);
+    }
+
+    #[test]
+    fn repeated_terms_share_a_placeholder() {
+        let glossary = glossary(&[("ぺこら", "Pekora"), ("ぺこーら", "Pekora")]);
+
+        let (protected, substitutions) = glossary.protect("ぺこらとぺこーら");
+
+        assert_eq!(protected, "[#0]と[#0]");
+        assert_eq!(substitutions, vec!["Pekora"]);
+    }
+
+    #[test]
+    fn ascii_terms_only_match_whole_words() {
+        let glossary = glossary(&[("Ina", "Ina")]);
+
+        let (protected, substitutions) = glossary.protect("Ina is in Argentina");
+
+        assert_eq!(protected, "[#0] is in Argentina");
+        assert_eq!(substitutions, vec!["Ina"]);
+    }
+
+    #[test]
+    fn restore_tolerates_mangled_placeholders() {
+        let substitutions = vec!["Pekora".to_owned()];
+
+        assert_eq!(
+            Glossary::restore("[ # 0 ] is here", &substitutions),
+            "Pekora is here"
+        );
+        assert_eq!(
+            Glossary::restore("[#3] is here", &substitutions),
+            "[#3] is here"
+        );
+    }
+
+    #[test]
+    fn empty_glossary_leaves_text_alone() {
+        let glossary = glossary(&[]);
+
+        assert_eq!(
+            glossary.protect("ぺこら"),
+            ("ぺこら".to_owned(), Vec::new())
+        );
+        assert_eq!(Glossary::restore("[#0]", &[]), "[#0]");
+    }
+}
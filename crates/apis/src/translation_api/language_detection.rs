@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+
+use once_cell::sync::OnceCell;
+
+/// Number of trigrams kept per language profile.
+const PROFILE_SIZE: usize = 300;
+/// Minimum number of letters needed before the n-gram model is trusted.
+const MIN_LATIN_LETTERS: usize = 12;
+/// Minimum share of letters a script needs to decide the language on its own.
+const SCRIPT_THRESHOLD: f32 = 0.3;
+
+/// Sample text used to build the trigram profile of each Latin-script language.
+const SAMPLES: &[(&str, &str)] = &[
+    (
+        "en",
+        "the stream will start in a few minutes so please wait for me everyone thank you \
+         for watching today and see you tomorrow i am going to play the new game with my \
+         friends this is the first time that we have been able to do this together it was \
+         really fun and i hope that you enjoyed it as much as i did please check out the \
+         announcement about the concert there are still some tickets left which you can \
+         get from the website what do you think about that i think it would be great",
+    ),
+    (
+        "id",
+        "terima kasih sudah menonton siaran hari ini sampai jumpa besok aku akan main game \
+         baru dengan teman teman ini adalah pertama kalinya kita bisa melakukan ini bersama \
+         sangat seru dan aku harap kalian juga menikmatinya jangan lupa untuk melihat \
+         pengumuman tentang konser yang akan datang masih ada beberapa tiket yang bisa \
+         kalian dapatkan dari situs web bagaimana menurut kalian tentang itu aku pikir itu \
+         akan sangat bagus sekali karena kita sudah lama tidak bertemu dengan semuanya",
+    ),
+    (
+        "de",
+        "der stream beginnt in ein paar minuten also wartet bitte auf mich danke dass ihr \
+         heute zugeschaut habt und bis morgen ich werde das neue spiel mit meinen freunden \
+         spielen das ist das erste mal dass wir das zusammen machen konnten es hat wirklich \
+         spass gemacht und ich hoffe dass es euch auch gefallen hat schaut euch bitte die \
+         ankündigung zum konzert an es gibt noch einige tickets die ihr auf der webseite \
+         bekommen könnt was haltet ihr davon ich denke das wäre wirklich toll",
+    ),
+    (
+        "es",
+        "el directo empieza en unos minutos así que esperadme por favor gracias por ver el \
+         stream de hoy y nos vemos mañana voy a jugar el nuevo juego con mis amigos es la \
+         primera vez que podemos hacer esto juntos fue muy divertido y espero que lo hayáis \
+         disfrutado tanto como yo por favor mirad el anuncio sobre el concierto todavía \
+         quedan algunas entradas que podéis conseguir en la página web qué pensáis de eso \
+         creo que sería genial",
+    ),
+    (
+        "fr",
+        "le live commence dans quelques minutes alors attendez moi s'il vous plait merci \
+         d'avoir regardé aujourd'hui et à demain je vais jouer au nouveau jeu avec mes amis \
+         c'est la première fois que nous pouvons faire cela ensemble c'était vraiment \
+         amusant et j'espère que vous avez aimé autant que moi regardez l'annonce du concert \
+         il reste encore quelques billets que vous pouvez obtenir sur le site qu'est ce que \
+         vous en pensez je pense que ce serait génial",
+    ),
+    (
+        "pt",
+        "a live vai começar em alguns minutos então esperem por mim por favor obrigado por \
+         assistir hoje e até amanhã eu vou jogar o novo jogo com os meus amigos é a primeira \
+         vez que conseguimos fazer isso juntos foi muito divertido e espero que vocês tenham \
+         gostado tanto quanto eu vejam o anúncio sobre o show ainda há alguns ingressos que \
+         vocês podem conseguir no site o que vocês acham disso eu acho que seria ótimo",
+    ),
+    (
+        "it",
+        "la diretta inizia tra pochi minuti quindi aspettatemi per favore grazie per aver \
+         guardato oggi e ci vediamo domani giocherò al nuovo gioco con i miei amici è la \
+         prima volta che riusciamo a farlo insieme è stato davvero divertente e spero che vi \
+         sia piaciuto quanto a me guardate l'annuncio del concerto ci sono ancora alcuni \
+         biglietti che potete prendere sul sito cosa ne pensate secondo me sarebbe fantastico",
+    ),
+    (
+        "nl",
+        "de stream begint over een paar minuten dus wacht even op mij bedankt voor het \
+         kijken vandaag en tot morgen ik ga het nieuwe spel spelen met mijn vrienden dit is \
+         de eerste keer dat we dit samen kunnen doen het was echt leuk en ik hoop dat jullie \
+         er net zo van genoten hebben als ik kijk even naar de aankondiging over het concert \
+         er zijn nog een paar kaartjes die jullie op de website kunnen krijgen wat vinden \
+         jullie daarvan ik denk dat het geweldig zou zijn",
+    ),
+];
+
+#[derive(Debug, Default)]
+struct ScriptCounts {
+    latin: usize,
+    kana: usize,
+    han: usize,
+    hangul: usize,
+    cyrillic: usize,
+    thai: usize,
+    arabic: usize,
+    total: usize,
+}
+
+impl ScriptCounts {
+    fn count(text: &str) -> Self {
+        let mut counts = Self::default();
+
+        for c in text.chars().filter(|c| c.is_alphabetic()) {
+            counts.total += 1;
+
+            match c as u32 {
+                0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => counts.kana += 1,
+                0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF => counts.han += 1,
+                0xAC00..=0xD7AF | 0x1100..=0x11FF | 0x3130..=0x318F => counts.hangul += 1,
+                0x0400..=0x04FF => counts.cyrillic += 1,
+                0x0E00..=0x0E7F => counts.thai += 1,
+                0x0600..=0x06FF => counts.arabic += 1,
+                _ if c.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&c) => {
+                    counts.latin += 1
+                }
+                _ => (),
+            }
+        }
+
+        counts
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn share(&self, count: usize) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            count as f32 / self.total as f32
+        }
+    }
+}
+
+/// Guesses the language of a text without any network requests.
+///
+/// Non-Latin scripts are identified by their Unicode ranges, while Latin-script
+/// languages are told apart by comparing their trigram frequencies to built-in profiles.
+/// Returns `None` if the text is too short or ambiguous to tell.
+#[must_use]
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let text = strip_noise(text);
+    let counts = ScriptCounts::count(&text);
+
+    if counts.total == 0 {
+        return None;
+    }
+
+    if counts.kana > 0 && counts.share(counts.kana + counts.han) >= SCRIPT_THRESHOLD {
+        return Some("ja");
+    }
+
+    let scripts = [
+        (counts.hangul, "ko"),
+        (counts.han, "zh"),
+        (counts.cyrillic, "ru"),
+        (counts.thai, "th"),
+        (counts.arabic, "ar"),
+    ];
+
+    if let Some((_, lang)) = scripts
+        .iter()
+        .filter(|(count, _)| counts.share(*count) >= SCRIPT_THRESHOLD)
+        .max_by_key(|(count, _)| *count)
+    {
+        return Some(lang);
+    }
+
+    if counts.latin < MIN_LATIN_LETTERS {
+        return None;
+    }
+
+    detect_latin_language(&text)
+}
+
+fn detect_latin_language(text: &str) -> Option<&'static str> {
+    let profile = rank_trigrams(text);
+
+    profiles()
+        .iter()
+        .map(|(lang, reference)| (*lang, out_of_place_distance(&profile, reference)))
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(lang, _)| lang)
+}
+
+fn profiles() -> &'static [(&'static str, HashMap<String, usize>)] {
+    static PROFILES: OnceCell<Vec<(&'static str, HashMap<String, usize>)>> = OnceCell::new();
+
+    PROFILES.get_or_init(|| {
+        SAMPLES
+            .iter()
+            .map(|(lang, sample)| (*lang, rank_trigrams(sample)))
+            .collect()
+    })
+}
+
+/// Removes links, mentions, hashtags and other tokens that don't say anything about the language.
+fn strip_noise(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|word| {
+            !(word.starts_with("http")
+                || word.starts_with('@')
+                || word.starts_with('#')
+                || word.starts_with('<'))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Maps the most common trigrams of the text to their rank.
+fn rank_trigrams(text: &str) -> HashMap<String, usize> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for word in text
+        .to_lowercase()
+        .split(|c: char| !c.is_alphabetic() && c != '\'')
+        .filter(|w| !w.is_empty())
+    {
+        let padded = format!(" {} ", word).chars().collect::<Vec<_>>();
+
+        for window in padded.windows(3) {
+            *counts.entry(window.iter().collect()).or_insert(0) += 1;
+        }
+    }
+
+    let mut ranked = counts.into_iter().collect::<Vec<_>>();
+    ranked.sort_unstable_by(|(a_gram, a), (b_gram, b)| b.cmp(a).then_with(|| a_gram.cmp(b_gram)));
+
+    ranked
+        .into_iter()
+        .take(PROFILE_SIZE)
+        .enumerate()
+        .map(|(rank, (gram, _))| (gram, rank))
+        .collect()
+}
+
+/// Sum of rank differences between two profiles, as described by Cavnar & Trenkle.
+fn out_of_place_distance(
+    profile: &HashMap<String, usize>,
+    reference: &HashMap<String, usize>,
+) -> usize {
+    profile
+        .iter()
+        .map(|(gram, rank)| match reference.get(gram) {
+            Some(ref_rank) => rank.max(ref_rank) - rank.min(ref_rank),
+            None => PROFILE_SIZE,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_japanese() {
+        assert_eq!(
+            detect_language("今日の配信ありがとうございました！"),
+            Some("ja")
+        );
+        assert_eq!(detect_language("おつぺこ"), Some("ja"));
+    }
+
+    #[test]
+    fn detects_korean() {
+        assert_eq!(detect_language("오늘 방송 봐주셔서 감사합니다"), Some("ko"));
+    }
+
+    #[test]
+    fn detects_chinese() {
+        assert_eq!(detect_language("今天的直播谢谢大家"), Some("zh"));
+    }
+
+    #[test]
+    fn detects_latin_languages() {
+        assert_eq!(
+            detect_language("Thank you all for watching the stream today, see you tomorrow!"),
+            Some("en")
+        );
+        assert_eq!(
+            detect_language("Terima kasih sudah menonton siaran hari ini, sampai jumpa besok!"),
+            Some("id")
+        );
+    }
+
+    #[test]
+    fn kana_decides_mixed_japanese() {
+        // Kanji on their own would read as Chinese, the kana mark the text as Japanese.
+        assert_eq!(detect_language("新衣装の配信はこちら"), Some("ja"));
+        assert_eq!(
+            detect_language("Minecraft配信するよ！みんな来てね https://youtu.be/abc"),
+            Some("ja")
+        );
+    }
+
+    #[test]
+    fn mostly_latin_text_ignores_a_few_other_letters() {
+        assert_eq!(
+            detect_language("Thank you so much for coming to the concert yesterday ありがとう"),
+            Some("en")
+        );
+    }
+
+    #[test]
+    fn ignores_links_mentions_and_hashtags() {
+        assert_eq!(
+            detect_language("@usadapekora #ぺこらーと https://example.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn short_latin_text_is_ambiguous() {
+        assert_eq!(detect_language("lol ok"), None);
+        assert_eq!(detect_language(""), None);
+        assert_eq!(detect_language("123 !!!"), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{error::Error as StdError, io::ErrorKind, time::Duration};
+use std::{collections::HashMap, error::Error as StdError, io::ErrorKind, time::Duration};
 
 use anyhow::{anyhow, Context};
 use backoff::ExponentialBackoff;
@@ -6,8 +6,10 @@ use bytes::Bytes;
 use chrono::prelude::*;
 use futures::{Stream, StreamExt};
 use reqwest::{Client, Error, Response};
+use rusqlite::{params, Connection};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
+use serenity::model::{id::RoleId, misc::Mention};
 use tokio::{
     sync::{
         mpsc::{self, Sender, UnboundedReceiver, UnboundedSender},
@@ -17,13 +19,18 @@ use tokio::{
 };
 use tracing::{debug, debug_span, error, info, instrument, trace, warn, Instrument};
 
-use super::{discord_api::DiscordMessageData, translation_api::TranslationApi};
+use super::{discord_api::DiscordMessageData, holo_api::HoloApi, translation_api::detect_language};
 use utility::{
-    config::{self, Config},
+    config::{self, Config, FanartConfig, SensitiveContentPolicy, TweetKind},
     extensions::VecExt,
     here,
 };
 
+const TALENT_RULE_TAG: &str = "Hololive Talents";
+const FANART_RULE_TAG: &str = "Hololive Fanart";
+/// How long fanart is given to gather engagement when a minimum is configured.
+const FANART_ENGAGEMENT_DELAY: Duration = Duration::from_secs(30 * 60);
+
 pub struct TwitterApi;
 
 impl TwitterApi {
@@ -41,6 +48,10 @@ impl TwitterApi {
         let config_clone = config.clone();
         let exit_rx_clone = exit_receiver.clone();
 
+        let spaces_config = config.clone();
+        let spaces_sender = notifier_sender.clone();
+        let spaces_exit_rx = exit_receiver.clone();
+
         tokio::spawn(
             async move {
                 match Self::run(config, msg_tx, exit_receiver).await {
@@ -66,14 +77,21 @@ impl TwitterApi {
             }
             .instrument(debug_span!("Twitter message consumer")),
         );
+
+        tokio::spawn(
+            async move {
+                match Self::spaces_notifier(spaces_config, spaces_sender, spaces_exit_rx).await {
+                    Ok(_) => (),
+                    Err(e) => {
+                        error!("{:?}", e);
+                    }
+                }
+            }
+            .instrument(debug_span!("Twitter Spaces notifier")),
+        );
     }
 
-    #[instrument(skip(config, message_sender, exit_receiver))]
-    async fn run(
-        config: Config,
-        message_sender: UnboundedSender<Bytes>,
-        mut exit_receiver: watch::Receiver<bool>,
-    ) -> anyhow::Result<()> {
+    fn create_client(config: &Config) -> anyhow::Result<Client> {
         use reqwest::header;
 
         let formatted_token = format!("Bearer {}", &config.twitter_token);
@@ -83,7 +101,7 @@ impl TwitterApi {
         auth_val.set_sensitive(true);
         headers.insert(header::AUTHORIZATION, auth_val);
 
-        let client = reqwest::ClientBuilder::new()
+        reqwest::ClientBuilder::new()
             .user_agent(concat!(
                 env!("CARGO_PKG_NAME"),
                 "/",
@@ -91,9 +109,40 @@ impl TwitterApi {
             ))
             .default_headers(headers)
             .build()
+            .context(here!())
+    }
+
+    /// Fetches the plain text and language of a single tweet.
+    #[instrument(skip(config))]
+    pub async fn get_tweet_text(
+        config: &Config,
+        id: u64,
+    ) -> anyhow::Result<(String, Option<String>)> {
+        let client = Self::create_client(config)?;
+
+        let response = client
+            .get(&format!("https://api.twitter.com/2/tweets/{}", id))
+            .query(&[("tweet.fields", "author_id,created_at,lang")])
+            .send()
+            .await
             .context(here!())?;
 
-        Self::setup_rules(&client, &config.users).await?;
+        response.error_for_status_ref().context(here!())?;
+
+        let tweet: SingleTweetResponse = response.json().await.context(here!())?;
+
+        Ok((tweet.data.plain_text(), tweet.data.language()))
+    }
+
+    #[instrument(skip(config, message_sender, exit_receiver))]
+    async fn run(
+        config: Config,
+        message_sender: UnboundedSender<Bytes>,
+        mut exit_receiver: watch::Receiver<bool>,
+    ) -> anyhow::Result<()> {
+        let client = Self::create_client(&config)?;
+
+        Self::setup_rules(&client, &config).await?;
         debug!("Twitter rules set up!");
 
         'main: loop {
@@ -170,18 +219,25 @@ impl TwitterApi {
         notifier_sender: Sender<DiscordMessageData>,
         mut exit_receiver: watch::Receiver<bool>,
     ) -> anyhow::Result<()> {
-        let translator = match TranslationApi::new(&config) {
-            Ok(api) => api,
-            Err(e) => {
-                anyhow::bail!(e);
-            }
-        };
+        let min_engagement = config.fanart.as_ref().and_then(|f| f.min_engagement);
 
         loop {
             tokio::select! {
                 Some(msg) = message_receiver.recv() => {
                     trace!("Message received from producer!");
-                    match Self::parse_message(&msg, &config.users, &translator).await {
+                    match Self::parse_message(&msg, &config) {
+                        Ok(Some(DiscordMessageData::Fanart(fanart))) => match min_engagement {
+                            Some(min) => Self::post_fanart_if_engaging(
+                                config.clone(),
+                                fanart,
+                                min,
+                                notifier_sender.clone(),
+                            ),
+                            None => notifier_sender
+                                .send(DiscordMessageData::Fanart(fanart))
+                                .await
+                                .context(here!())?,
+                        },
                         Ok(Some(discord_message)) => {
                             trace!("Tweet successfully parsed!");
                             notifier_sender
@@ -206,6 +262,240 @@ impl TwitterApi {
         Ok(())
     }
 
+    /// Posts the fanart once it's had time to gather engagement, if it got enough of it.
+    /// Fanart still waiting when the bot shuts down isn't posted.
+    fn post_fanart_if_engaging(
+        config: Config,
+        fanart: HoloFanart,
+        min_engagement: u64,
+        notifier_sender: Sender<DiscordMessageData>,
+    ) {
+        tokio::spawn(
+            async move {
+                tokio::time::sleep(FANART_ENGAGEMENT_DELAY).await;
+
+                match Self::get_engagement(&config, fanart.id).await {
+                    Ok(engagement) if engagement >= min_engagement => {
+                        if let Err(e) = notifier_sender
+                            .send(DiscordMessageData::Fanart(fanart))
+                            .await
+                        {
+                            error!("{:?}", e);
+                        }
+                    }
+                    Ok(engagement) => debug!(
+                        engagement,
+                        "Skipping fanart of {} without enough engagement.",
+                        fanart.user.display_name
+                    ),
+                    Err(e) => error!("{:?}", e),
+                }
+            }
+            .instrument(debug_span!("Fanart engagement check")),
+        );
+    }
+
+    /// Fetches the current likes, retweets, replies and quotes of a tweet.
+    #[instrument(skip(config))]
+    async fn get_engagement(config: &Config, id: u64) -> anyhow::Result<u64> {
+        let client = Self::create_client(config)?;
+
+        let response = client
+            .get(&format!("https://api.twitter.com/2/tweets/{}", id))
+            .query(&[("tweet.fields", "public_metrics")])
+            .send()
+            .await
+            .context(here!())?;
+
+        response.error_for_status_ref().context(here!())?;
+
+        let tweet: TweetMetricsResponse = response.json().await.context(here!())?;
+
+        Ok(tweet.data.public_metrics.total())
+    }
+
+    #[instrument(skip(config, notifier_sender, exit_receiver))]
+    async fn spaces_notifier(
+        config: Config,
+        notifier_sender: Sender<DiscordMessageData>,
+        mut exit_receiver: watch::Receiver<bool>,
+    ) -> anyhow::Result<()> {
+        let client = Self::create_client(&config)?;
+        let handle = config.get_database_handle()?;
+
+        // Remembered across restarts, so current Spaces aren't announced again.
+        let mut notified_spaces = Self::load_notified_spaces(&handle)?;
+
+        loop {
+            let mut next_start: Option<DateTime<Utc>> = None;
+
+            match Self::get_spaces(&client, &config.users).await {
+                Ok(spaces) => {
+                    // Forget about Spaces that have ended.
+                    let ended = notified_spaces
+                        .keys()
+                        .filter(|id| !spaces.iter().any(|s| &s.id == *id))
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    for id in ended {
+                        notified_spaces.remove(&id);
+
+                        if let Err(e) = Self::forget_space(&handle, &id) {
+                            error!("{:?}", e);
+                        }
+                    }
+
+                    for space in spaces {
+                        if let Some(start) = space.scheduled_start {
+                            if start > Utc::now() {
+                                next_start = Some(next_start.map_or(start, |n| n.min(start)));
+                            }
+                        }
+
+                        if notified_spaces.get(&space.id) == Some(&space.state) {
+                            continue;
+                        }
+
+                        notified_spaces.insert(space.id.clone(), space.state);
+
+                        if let Err(e) = Self::save_notified_space(&handle, &space.id, space.state) {
+                            error!("{:?}", e);
+                        }
+
+                        info!(
+                            "{} Twitter Space from {}.",
+                            match space.state {
+                                SpaceState::Scheduled => "New scheduled",
+                                SpaceState::Live => "Live",
+                            },
+                            space.user.display_name
+                        );
+
+                        notifier_sender
+                            .send(DiscordMessageData::Space(space))
+                            .await
+                            .context(here!())?;
+                    }
+                }
+                Err(e) => warn!("{:?}", e),
+            }
+
+            // Poll again right when the next scheduled Space is due to start, like streams,
+            // but not so often that Twitter rate limits us.
+            let sleep_duration = HoloApi::notifier_sleep(next_start).max(Duration::from_secs(10));
+
+            tokio::select! {
+                _ = tokio::time::sleep(sleep_duration) => (),
+
+                res = exit_receiver.changed() => {
+                    if let Err(e) = res {
+                        error!("{:?}", e);
+                    }
+                    break;
+                }
+            }
+        }
+
+        info!(task = "Twitter Spaces notifier", "Shutting down.");
+        Ok(())
+    }
+
+    fn load_notified_spaces(handle: &Connection) -> anyhow::Result<HashMap<String, SpaceState>> {
+        let mut stmt = handle
+            .prepare("SELECT space_id, live FROM NotifiedSpaces")
+            .context(here!())?;
+
+        let spaces = stmt
+            .query_map([], |row| {
+                let live: bool = row.get(1)?;
+
+                Ok((
+                    row.get(0)?,
+                    if live {
+                        SpaceState::Live
+                    } else {
+                        SpaceState::Scheduled
+                    },
+                ))
+            })
+            .context(here!())?
+            .collect::<Result<_, _>>()
+            .context(here!())?;
+
+        Ok(spaces)
+    }
+
+    fn save_notified_space(handle: &Connection, id: &str, state: SpaceState) -> anyhow::Result<()> {
+        handle
+            .execute(
+                "INSERT OR REPLACE INTO NotifiedSpaces (space_id, live) VALUES (?, ?)",
+                params![id, state == SpaceState::Live],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    fn forget_space(handle: &Connection, id: &str) -> anyhow::Result<()> {
+        handle
+            .execute("DELETE FROM NotifiedSpaces WHERE space_id = ?", [id])
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(client, users))]
+    async fn get_spaces(client: &Client, users: &[config::User]) -> anyhow::Result<Vec<HoloSpace>> {
+        let mut spaces = Vec::new();
+
+        // The endpoint accepts at most 100 user IDs per request.
+        for chunk in users.chunks(100) {
+            let user_ids = chunk
+                .iter()
+                .map(|u| u.twitter_id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let response = client
+                .get("https://api.twitter.com/2/spaces/by/creator_ids")
+                .query(&[
+                    ("user_ids", user_ids.as_str()),
+                    (
+                        "space.fields",
+                        "title,state,scheduled_start,started_at,creator_id",
+                    ),
+                ])
+                .send()
+                .await
+                .context(here!())?;
+
+            Self::check_rate_limit(&response)?;
+            let response = Self::validate_response::<SpacesResponse>(response).await?;
+
+            spaces.extend(response.data.into_iter().filter_map(|space| {
+                let state = match space.state.as_str() {
+                    "scheduled" => SpaceState::Scheduled,
+                    "live" => SpaceState::Live,
+                    _ => return None,
+                };
+
+                let user = users.iter().find(|u| u.twitter_id == space.creator_id)?;
+
+                Some(HoloSpace {
+                    id: space.id,
+                    user: user.clone(),
+                    title: space.title,
+                    state,
+                    scheduled_start: space.scheduled_start,
+                    started_at: space.started_at,
+                })
+            }));
+        }
+
+        Ok(spaces)
+    }
+
     #[instrument(skip(client))]
     async fn connect(client: &Client) -> anyhow::Result<impl Stream<Item = Result<Bytes, Error>>> {
         let backoff_config = ExponentialBackoff {
@@ -220,11 +510,15 @@ impl TwitterApi {
             let response = client
                 .get("https://api.twitter.com/2/tweets/search/stream")
                 .query(&[
-                    ("expansions", "attachments.media_keys,referenced_tweets.id"),
+                    (
+                        "expansions",
+                        "attachments.media_keys,referenced_tweets.id,author_id",
+                    ),
                     ("media.fields", "url"),
+                    ("user.fields", "name,username,profile_image_url"),
                     (
                         "tweet.fields",
-                        "author_id,created_at,lang,in_reply_to_user_id,referenced_tweets",
+                        "author_id,created_at,lang,in_reply_to_user_id,referenced_tweets,possibly_sensitive,entities",
                     ),
                 ])
                 .send()
@@ -250,12 +544,13 @@ impl TwitterApi {
     }
 
     #[allow(clippy::too_many_lines)]
-    #[instrument(skip(message, users, translator))]
-    async fn parse_message(
+    #[instrument(skip(message, config))]
+    fn parse_message(
         message: &Bytes,
-        users: &[config::User],
-        translator: &TranslationApi,
+        config: &Config,
     ) -> anyhow::Result<Option<DiscordMessageData>> {
+        let users = &config.users;
+
         let deserializer = &mut serde_json::Deserializer::from_slice(&message);
         let response: Result<Tweet, _> = serde_path_to_error::deserialize(deserializer);
 
@@ -275,6 +570,21 @@ impl TwitterApi {
             }
         };
 
+        let is_talent = users.iter().any(|u| u.twitter_id == message.data.author_id);
+
+        // Tweets from artists only reach us through the fanart rules.
+        if !is_talent
+            && message
+                .matching_rules
+                .iter()
+                .any(|r| r.tag.starts_with(FANART_RULE_TAG))
+        {
+            return match &config.fanart {
+                Some(fanart_config) => Ok(Self::parse_fanart(message, users, fanart_config)),
+                None => Ok(None),
+            };
+        }
+
         // Find who made the tweet.
         let user = users
             .iter()
@@ -316,7 +626,7 @@ impl TwitterApi {
 
                     return Ok(Some(DiscordMessageData::ScheduleUpdate(ScheduleUpdate {
                         twitter_id: user.twitter_id,
-                        tweet_text: message.data.text,
+                        tweet_text: message.data.render_text(users),
                         schedule_image,
                         tweet_link: format!(
                             "https://twitter.com/{}/status/{}",
@@ -330,6 +640,7 @@ impl TwitterApi {
 
         // Check if we're replying to another talent.
         let mut replied_to: Option<HoloTweetReference> = None;
+        let mut kind = TweetKind::Original;
 
         if !message.data.referenced_tweets.is_empty() {
             let reference = message
@@ -339,14 +650,20 @@ impl TwitterApi {
                 .ok_or_else(|| anyhow!("Can't reach tweet reference!").context(here!()))?;
 
             let replied_to_user = match reference.reply_type.as_str() {
-                "replied_to" => message
-                    .data
-                    .in_reply_to_user_id
-                    .ok_or_else(|| {
-                        anyhow!("Tweet reply didn't contain a in_reply_to_user_id field.")
-                    })
-                    .context(here!())?,
+                "replied_to" => {
+                    kind = TweetKind::Reply;
+
+                    message
+                        .data
+                        .in_reply_to_user_id
+                        .ok_or_else(|| {
+                            anyhow!("Tweet reply didn't contain a in_reply_to_user_id field.")
+                        })
+                        .context(here!())?
+                }
                 "quoted" => {
+                    kind = TweetKind::Quote;
+
                     message
                         .includes
                         .as_ref()
@@ -389,81 +706,122 @@ impl TwitterApi {
             }
         }
 
-        // Check if translation is necessary.
-        let mut translation: Option<String> = None;
-
-        if let Some(lang) = message.data.lang {
-            match lang.as_str() {
-                "in" | "id" | "de" | "ja" | "jp" => {
-                    if let Ok(tl) = translator
-                        .get_translator_for_lang(&lang)
-                        .translate(&message.data.text, &lang)
-                        .await
-                    {
-                        translation = Some(tl);
-                    }
-                }
-                _ => (),
-            }
-        }
+        let plain_text = message.data.plain_text();
+        let lang = message.data.language();
 
         info!("New tweet from {}.", user.display_name);
 
         let tweet = HoloTweet {
             id: message.data.id,
             user: user.clone(),
-            text: message.data.text,
+            text: message.data.render_text(users),
             link: format!(
                 "https://twitter.com/{}/status/{}",
                 user.twitter_id, message.data.id
             ),
             timestamp: message.data.created_at,
             media,
-            translation,
+            plain_text,
             replied_to,
+            kind,
+            lang,
+            translations: HashMap::new(),
         };
 
         Ok(Some(DiscordMessageData::Tweet(tweet)))
     }
 
-    #[instrument(skip(client, users))]
-    async fn setup_rules(client: &Client, users: &[config::User]) -> anyhow::Result<()> {
-        let mut rules = vec![];
-        let mut current_rule = String::with_capacity(512);
-        let mut i = 0;
+    #[instrument(skip(message, users, fanart_config))]
+    fn parse_fanart(
+        message: Tweet,
+        users: &[config::User],
+        fanart_config: &FanartConfig,
+    ) -> Option<DiscordMessageData> {
+        let hashtags = message
+            .data
+            .entities
+            .as_ref()
+            .map(|e| e.hashtags.as_slice())
+            .unwrap_or_default();
+
+        // Find which talent the fanart is tagged for.
+        let user = users.iter().find(|u| {
+            u.fanart_hashtag.as_ref().map_or(false, |tag| {
+                let tag = tag.trim_start_matches('#');
+                hashtags.iter().any(|h| h.tag.eq_ignore_ascii_case(tag))
+            })
+        })?;
+
+        if message.data.possibly_sensitive
+            && fanart_config.sensitive_content == SensitiveContentPolicy::Filter
+        {
+            debug!(
+                "Skipping possibly sensitive fanart of {}.",
+                user.display_name
+            );
+            return None;
+        }
 
-        while i < users.len() {
-            let user = &users
-                .get(i)
-                .ok_or_else(|| anyhow!("Couldn't get user!"))
-                .context(here!())?;
-            let new_segment;
+        let includes = message.includes?;
 
-            if current_rule.is_empty() {
-                current_rule += "-is:retweet (";
-                new_segment = format!("from:{}", user.twitter_id)
-            } else {
-                new_segment = format!(" OR from:{}", user.twitter_id)
-            }
+        let artist = includes
+            .users
+            .into_iter()
+            .find(|u| u.id == message.data.author_id)?;
 
-            if current_rule.len() + new_segment.len() < 511 {
-                current_rule += &new_segment;
-                i += 1;
-            } else {
-                rules.push(Rule {
-                    value: current_rule.clone() + ")",
-                    tag: format!("Hololive Talents {}", rules.len() + 1),
-                });
+        let media = includes
+            .media
+            .into_iter()
+            .filter(|m| m.media_type == "photo")
+            .filter_map(|m| m.url)
+            .collect::<Vec<_>>();
 
-                current_rule.clear();
-            }
+        if media.is_empty() {
+            return None;
         }
 
-        if !current_rule.is_empty() {
-            rules.push(Rule {
-                value: current_rule.clone() + ")",
-                tag: format!("Hololive Talents {}", rules.len() + 1),
-            });
+        info!(
+            "New fanart of {} by @{}.",
+            user.display_name, artist.username
+        );
+
+        Some(DiscordMessageData::Fanart(HoloFanart {
+            id: message.data.id,
+            user: user.clone(),
+            artist_name: artist.name,
+            link: format!(
+                "https://twitter.com/{}/status/{}",
+                artist.username, message.data.id
+            ),
+            artist_handle: artist.username,
+            artist_icon: artist.profile_image_url,
+            text: message.data.render_text(users),
+            timestamp: message.data.created_at,
+            media,
+            spoiler: message.data.possibly_sensitive,
+        }))
+    }
+
+    #[instrument(skip(client, config))]
+    async fn setup_rules(client: &Client, config: &Config) -> anyhow::Result<()> {
+        let users = &config.users;
+
+        let mut rules = Self::build_rules(
+            "-is:retweet (",
+            users.iter().map(|u| format!("from:{}", u.twitter_id)),
+            TALENT_RULE_TAG,
+        );
+
+        // Fanart counts against the stream's tweet cap, so it's only asked for when it's posted.
+        if config.fanart.is_some() {
+            rules.extend(Self::build_rules(
+                "has:images -is:retweet -is:reply (",
+                users
+                    .iter()
+                    .filter_map(|u| u.fanart_hashtag.as_ref())
+                    .map(|tag| format!("#{}", tag.trim_start_matches('#'))),
+                FANART_RULE_TAG,
+            ));
         }
 
         let existing_rules = Self::get_rules(client).await?;
@@ -501,6 +859,46 @@ impl TwitterApi {
         Ok(())
     }
 
+    fn build_rules<I>(prefix: &str, segments: I, tag: &str) -> Vec<Rule>
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut rules = vec![];
+        let mut current_rule = String::with_capacity(512);
+
+        for segment in segments {
+            let separator = if current_rule.is_empty() { "" } else { " OR " };
+
+            if !current_rule.is_empty()
+                && current_rule.len() + separator.len() + segment.len() >= 511
+            {
+                rules.push(Rule {
+                    value: current_rule.clone() + ")",
+                    tag: format!("{} {}", tag, rules.len() + 1),
+                });
+
+                current_rule.clear();
+            }
+
+            if current_rule.is_empty() {
+                current_rule += prefix;
+            } else {
+                current_rule += " OR ";
+            }
+
+            current_rule += &segment;
+        }
+
+        if !current_rule.is_empty() {
+            rules.push(Rule {
+                value: current_rule + ")",
+                tag: format!("{} {}", tag, rules.len() + 1),
+            });
+        }
+
+        rules
+    }
+
     #[instrument(skip(client))]
     async fn get_rules(client: &Client) -> anyhow::Result<Vec<RemoteRule>> {
         let response = client
@@ -671,8 +1069,50 @@ pub struct HoloTweet {
     pub link: String,
     pub timestamp: DateTime<Utc>,
     pub media: Vec<String>,
-    pub translation: Option<String>,
+    /// The unformatted tweet text, used for translations.
+    pub plain_text: String,
     pub replied_to: Option<HoloTweetReference>,
+    pub kind: TweetKind,
+    pub lang: Option<String>,
+    /// Translations of the tweet by target language, filled in before it's posted.
+    pub translations: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+pub struct HoloFanart {
+    pub id: u64,
+    pub user: config::User,
+    pub artist_name: String,
+    pub artist_handle: String,
+    pub artist_icon: Option<String>,
+    pub text: String,
+    pub link: String,
+    pub timestamp: DateTime<Utc>,
+    pub media: Vec<String>,
+    pub spoiler: bool,
+}
+
+#[derive(Debug)]
+pub struct HoloSpace {
+    pub id: String,
+    pub user: config::User,
+    pub title: Option<String>,
+    pub state: SpaceState,
+    pub scheduled_start: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+impl HoloSpace {
+    #[must_use]
+    pub fn link(&self) -> String {
+        format!("https://twitter.com/i/spaces/{}", self.id)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SpaceState {
+    Scheduled,
+    Live,
 }
 
 #[derive(Debug)]
@@ -734,6 +1174,39 @@ struct Tweet {
     matching_rules: Vec<MatchingRule>,
 }
 
+#[derive(Deserialize, Debug)]
+struct SingleTweetResponse {
+    data: TweetInfo,
+}
+
+#[derive(Deserialize, Debug)]
+struct TweetMetricsResponse {
+    data: TweetMetricsInfo,
+}
+
+#[derive(Deserialize, Debug)]
+struct TweetMetricsInfo {
+    public_metrics: TweetMetrics,
+}
+
+#[derive(Deserialize, Debug)]
+struct TweetMetrics {
+    #[serde(default)]
+    retweet_count: u64,
+    #[serde(default)]
+    reply_count: u64,
+    #[serde(default)]
+    like_count: u64,
+    #[serde(default)]
+    quote_count: u64,
+}
+
+impl TweetMetrics {
+    fn total(&self) -> u64 {
+        self.retweet_count + self.reply_count + self.like_count + self.quote_count
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct RuleRequestResponse {
@@ -745,6 +1218,29 @@ struct RuleRequestResponse {
     error: Option<ApiError>,
 }
 
+#[derive(Deserialize, Debug)]
+struct SpacesResponse {
+    #[serde(default = "Vec::new")]
+    data: Vec<SpaceInfo>,
+
+    #[serde(flatten)]
+    error: Option<ApiError>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Debug)]
+struct SpaceInfo {
+    id: String,
+    state: String,
+    title: Option<String>,
+    #[serde_as(as = "DisplayFromStr")]
+    creator_id: u64,
+    #[serde(default, with = "utility::serializers::opt_utc_datetime")]
+    scheduled_start: Option<DateTime<Utc>>,
+    #[serde(default, with = "utility::serializers::opt_utc_datetime")]
+    started_at: Option<DateTime<Utc>>,
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct RuleUpdateResponse {
@@ -772,6 +1268,145 @@ struct TweetInfo {
     in_reply_to_user_id: Option<u64>,
     #[serde(default = "Vec::new")]
     referenced_tweets: Vec<TweetReference>,
+    #[serde(default)]
+    possibly_sensitive: bool,
+    entities: Option<TweetEntities>,
+}
+
+impl TweetInfo {
+    /// Returns the tweet text with HTML entities unescaped.
+    fn plain_text(&self) -> String {
+        unescape_html(&self.text)
+    }
+
+    /// Returns the language of the tweet, guessing it if Twitter didn't tag it.
+    fn language(&self) -> Option<String> {
+        match self.lang.as_deref() {
+            None | Some("und") => detect_language(&self.plain_text()).map(str::to_owned),
+            Some(lang) => Some(lang.to_owned()),
+        }
+    }
+
+    /// Renders the tweet text as Discord markdown, expanding links and linking mentions and hashtags.
+    fn render_text(&self, users: &[config::User]) -> String {
+        let entities = match &self.entities {
+            Some(e) => e,
+            None => return self.plain_text(),
+        };
+
+        let mut replacements = Vec::new();
+
+        for url in &entities.urls {
+            // Attached media is already shown in the embed.
+            let replacement = if url.media_key.is_some() {
+                String::new()
+            } else {
+                let expanded = url.expanded_url.as_ref().unwrap_or(&url.url);
+                let display = url.display_url.as_ref().unwrap_or(expanded);
+
+                format!("[{}]({})", display, expanded)
+            };
+
+            replacements.push((url.start, url.end, url.url.clone(), replacement));
+        }
+
+        for mention in &entities.mentions {
+            let profile = format!("[@{0}](https://twitter.com/{0})", mention.username);
+
+            let replacement = match users
+                .iter()
+                .find(|u| u.twitter_handle.eq_ignore_ascii_case(&mention.username))
+            {
+                Some(user) => format!("{} ({})", profile, Mention::from(RoleId(user.discord_role))),
+                None => profile,
+            };
+
+            replacements.push((
+                mention.start,
+                mention.end,
+                format!("@{}", mention.username),
+                replacement,
+            ));
+        }
+
+        for hashtag in &entities.hashtags {
+            replacements.push((
+                hashtag.start,
+                hashtag.end,
+                format!("#{}", hashtag.tag),
+                format!("[#{0}](https://twitter.com/hashtag/{0})", hashtag.tag),
+            ));
+        }
+
+        replacements.sort_unstable_by_key(|(start, ..)| *start);
+
+        // Entity offsets are given in characters, not bytes.
+        let chars = self.text.chars().collect::<Vec<_>>();
+        let mut rendered = String::with_capacity(self.text.len() * 2);
+        let mut position = 0;
+
+        for (start, end, original, replacement) in replacements {
+            if start < position || end > chars.len() {
+                continue;
+            }
+
+            let entity_text = chars[start..end].iter().collect::<String>();
+
+            // Skip entities whose offsets don't line up with the text.
+            if !entity_text.eq_ignore_ascii_case(&original) {
+                continue;
+            }
+
+            rendered += &unescape_html(&chars[position..start].iter().collect::<String>());
+            rendered += &replacement;
+            position = end;
+        }
+
+        rendered += &unescape_html(&chars[position..].iter().collect::<String>());
+        rendered.trim_end().to_owned()
+    }
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[derive(Deserialize, Debug)]
+struct TweetEntities {
+    #[serde(default = "Vec::new")]
+    urls: Vec<UrlEntity>,
+    #[serde(default = "Vec::new")]
+    mentions: Vec<MentionEntity>,
+    #[serde(default = "Vec::new")]
+    hashtags: Vec<HashtagEntity>,
+}
+
+#[derive(Deserialize, Debug)]
+struct UrlEntity {
+    start: usize,
+    end: usize,
+    url: String,
+    expanded_url: Option<String>,
+    display_url: Option<String>,
+    media_key: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct MentionEntity {
+    start: usize,
+    end: usize,
+    username: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct HashtagEntity {
+    start: usize,
+    end: usize,
+    tag: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -795,6 +1430,18 @@ struct Expansions {
     media: Vec<MediaInfo>,
     #[serde(default = "Vec::new")]
     tweets: Vec<TweetInfo>,
+    #[serde(default = "Vec::new")]
+    users: Vec<TwitterUser>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Debug)]
+struct TwitterUser {
+    #[serde_as(as = "DisplayFromStr")]
+    id: u64,
+    name: String,
+    username: String,
+    profile_image_url: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -860,6 +1507,12 @@ impl CanContainError for RuleRequestResponse {
     }
 }
 
+impl CanContainError for SpacesResponse {
+    fn get_error(&self) -> Option<&ApiError> {
+        self.error.as_ref()
+    }
+}
+
 impl CanContainError for RuleUpdateResponse {
     fn get_error(&self) -> Option<&ApiError> {
         self.error.as_ref()
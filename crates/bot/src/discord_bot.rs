@@ -21,6 +21,7 @@ use tracing::{debug, error, info, instrument, warn};
 use apis::{
     holo_api::{Livestream, StreamUpdate},
     meme_api::MemeApi,
+    translation_api::TranslationApi,
 };
 use commands::util::*;
 use utility::{
@@ -43,6 +44,7 @@ impl DiscordBot {
         stream_update: broadcast::Sender<StreamUpdate>,
         reminder_sender: mpsc::Receiver<EntryEvent<u64, Reminder>>,
         index_receiver: watch::Receiver<HashMap<u32, Livestream>>,
+        translator: Arc<TranslationApi>,
         guild_ready: oneshot::Sender<()>,
         exit_receiver: watch::Receiver<bool>,
     ) -> anyhow::Result<(JoinHandle<()>, Arc<CacheAndHttp>)> {
@@ -85,6 +87,7 @@ impl DiscordBot {
                 stream_update,
                 reminder_sender,
                 index_receiver,
+                translator,
                 exit_receiver,
             )
             .await
@@ -101,13 +104,21 @@ impl DiscordBot {
         Ok((task, cache))
     }
 
-    #[instrument(skip(client, config, stream_update, index_receiver, exit_receiver))]
+    #[instrument(skip(
+        client,
+        config,
+        stream_update,
+        index_receiver,
+        translator,
+        exit_receiver
+    ))]
     async fn run(
         mut client: Client,
         config: Config,
         stream_update: broadcast::Sender<StreamUpdate>,
         reminder_sender: mpsc::Receiver<EntryEvent<u64, Reminder>>,
         index_receiver: watch::Receiver<HashMap<u32, Livestream>>,
+        translator: Arc<TranslationApi>,
         mut exit_receiver: watch::Receiver<bool>,
     ) -> anyhow::Result<()> {
         {
@@ -116,6 +127,7 @@ impl DiscordBot {
             let db_handle = config.get_database_handle()?;
 
             data.insert::<MemeApi>(MemeApi::new(&config)?);
+            data.insert::<TranslationApi>(translator);
             data.insert::<Quotes>(Quotes::load_from_database(&db_handle)?.into());
             data.insert::<EmojiUsage>(EmojiUsage::load_from_database(&db_handle)?.into());
 
@@ -372,6 +384,18 @@ impl EventHandler for Handler {
         } else {
             error!("Failed to get notification sender!");
         }
+
+        std::mem::drop(sender_lock);
+        std::mem::drop(data);
+
+        // The roster may have changed since the role menus were posted.
+        let config = self.config.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = commands::role_menu::refresh_role_menus(&ctx, &config, guild.id).await {
+                error!("{:?}", e);
+            }
+        });
     }
 
     #[instrument(skip(self, ctx))]
@@ -407,7 +431,17 @@ impl EventHandler for Handler {
                 }
             }
 
-            InteractionType::MessageComponent => (),
+            InteractionType::MessageComponent => {
+                let config = self.config.clone();
+
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        commands::components::handle_component(&ctx, &request, &config).await
+                    {
+                        warn!(err = ?e, "Component interaction failed.");
+                    }
+                });
+            }
 
             _ => warn!("Unknown interaction type: {:#?}!", request.kind),
         }
@@ -0,0 +1,105 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+use serenity::{
+    builder::CreateEmbed,
+    model::interactions::{InteractionData, InteractionMessage},
+};
+
+use apis::{
+    discord_api::{DiscordApi, TweetView, TRANSLATION_FIELD, TWEET_VIEW_BUTTON},
+    translation_api::{COMPARE_TWEET_BUTTON, TRANSLATION_VOTE_BUTTON},
+};
+
+use super::{
+    prelude::*,
+    role_menu::{self, ROLE_MENU_SELECT},
+    translate,
+};
+
+/// Handles message components that have to keep working after the bot restarts.
+///
+/// Custom IDs have the form `<kind>:<args>`; components with unknown kinds are left
+/// to any collectors that may be waiting for them.
+#[instrument(skip(ctx, interaction, config))]
+pub async fn handle_component(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let custom_id = match &interaction.data {
+        Some(InteractionData::MessageComponent(d)) => d.custom_id.as_str(),
+        _ => return Ok(()),
+    };
+
+    let (kind, args) = custom_id.split_once(':').unwrap_or((custom_id, ""));
+
+    match kind {
+        COMPARE_TWEET_BUTTON => translate::compare_tweet(ctx, interaction, config, args).await,
+        TRANSLATION_VOTE_BUTTON => translate::record_vote(ctx, interaction, args).await,
+        TWEET_VIEW_BUTTON => switch_tweet_view(ctx, interaction, args).await,
+        ROLE_MENU_SELECT => role_menu::toggle_roles(ctx, interaction, config).await,
+        _ => Ok(()),
+    }
+}
+
+/// Switches a tweet post between showing the original text, the translation or both.
+async fn switch_tweet_view(ctx: &Ctx, interaction: &Interaction, args: &str) -> anyhow::Result<()> {
+    let view = TweetView::from_str(args).context(here!())?;
+
+    let message = match &interaction.message {
+        Some(InteractionMessage::Regular(m)) => m,
+        _ => {
+            return Err(anyhow!("Tweet view buttons must be on a regular message.").context(here!()))
+        }
+    };
+
+    let texts = {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        DiscordApi::load_translated_tweet(&handle, message.id)?
+    };
+
+    let (original, translation) = match texts {
+        Some(t) => t,
+        None => return Err(anyhow!("No translation saved for this tweet.").context(here!())),
+    };
+
+    let mut embed = match message.embeds.first() {
+        Some(e) => CreateEmbed::from(e.clone()),
+        None => return Err(anyhow!("Tweet post has no embed.").context(here!())),
+    };
+
+    // Keep all other fields, such as the tweet being replied to.
+    let mut fields = match embed.0.remove("fields") {
+        Some(Value::Array(fields)) => fields,
+        _ => Vec::new(),
+    };
+    fields.retain(|f| f.get("name").and_then(Value::as_str) != Some(TRANSLATION_FIELD));
+    embed.0.insert("fields", Value::Array(fields));
+    embed.0.remove("footer");
+
+    match view {
+        TweetView::Original => {
+            embed.description(original);
+        }
+        TweetView::Translation => {
+            embed.description(translation);
+            embed.footer(|f| f.text(TRANSLATION_FIELD));
+        }
+        TweetView::Both => {
+            embed.description(original);
+            embed.field(TRANSLATION_FIELD, translation, false);
+        }
+    }
+
+    Interaction::create_interaction_response(interaction, &ctx.http, |r| {
+        r.kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|d| d.embeds(vec![embed]))
+    })
+    .await
+    .context(here!())?;
+
+    Ok(())
+}
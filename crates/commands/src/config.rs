@@ -1,5 +1,11 @@
 use super::prelude::*;
 
+use std::{collections::HashMap, str::FromStr};
+
+use chrono::{NaiveTime, Utc};
+use serenity::builder::CreateEmbed;
+use utility::config::{EmbedTemplate, HoloBranch, ScheduleDigest, TemplateKind};
+
 interaction_setup! {
     name = "config",
     group = "utility",
@@ -13,6 +19,46 @@ interaction_setup! {
                 req command_name: String,
             ],
         ],
+        //! Notification template settings.
+        template: SubCommandGroup = [
+            //! Show what a notification looks like in this server.
+            preview: SubCommand = [
+                //! Notification to preview.
+                req kind: String = enum TemplateKind,
+            ],
+            //! Change the text of a notification. Use `-` to leave a part out.
+            edit: SubCommand = [
+                //! Notification to change.
+                req kind: String = enum TemplateKind,
+                //! Message text above the embed, such as `{mention}`.
+                content: String,
+                //! Title of the embed.
+                title: String,
+                //! Description of the embed.
+                description: String,
+                //! Footer of the embed.
+                footer: String,
+            ],
+            //! Go back to the default text of a notification.
+            reset: SubCommand = [
+                //! Notification to reset.
+                req kind: String = enum TemplateKind,
+            ],
+        ],
+        //! Daily schedule digest settings.
+        digest: SubCommandGroup = [
+            //! Post a daily digest of the next 24 hours of streams in this channel.
+            set: SubCommand = [
+                //! Local time to post the digest at, such as `18:00`.
+                req time: String,
+                //! Timezone of the time, such as `Europe/London` or `Asia/Tokyo`.
+                req timezone: String,
+                //! Only include talents from this branch.
+                branch: String = enum HoloBranch,
+            ],
+            //! Stop posting the daily digest in this server.
+            remove: SubCommand,
+        ],
     ],
     restrictions = [
         allowed_roles = [
@@ -56,8 +102,174 @@ async fn config(ctx: &Ctx, interaction: &Interaction, config: &Config) -> anyhow
                     }).await?;
                 }
             }
+        },
+        "template preview" => |kind: enum TemplateKind| {
+            let kind = kind.context(here!())?;
+            let handle = config.get_database_handle()?;
+            let template = EmbedTemplate::load(&handle, interaction.guild_id.unwrap(), kind)?;
+
+            show_template(ctx, interaction, config, &template, &format!("Preview of the `{}` template:", kind.to_string())).await?;
+        },
+        "template edit" => |kind: enum TemplateKind, content: String, title: String, description: String, footer: String| {
+            let kind = kind.context(here!())?;
+            let handle = config.get_database_handle()?;
+            let current = EmbedTemplate::load(&handle, interaction.guild_id.unwrap(), kind)?;
+
+            let template = EmbedTemplate {
+                content: merge_template_part(current.content, content),
+                title: merge_template_part(current.title, title),
+                description: merge_template_part(current.description, description),
+                footer: merge_template_part(current.footer, footer),
+            };
+
+            if let Some((part, limit)) = template.overlong_part(&sample_values(config)) {
+                interaction.create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d|
+                            d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                            .content(format!("The {} can't be longer than {} characters once the placeholders are filled in, the template wasn't changed.", part, limit)))
+                }).await?;
+
+                return Ok(());
+            }
+
+            template.save(&handle, interaction.guild_id.unwrap(), kind)?;
+
+            show_template(ctx, interaction, config, &template, &format!("Updated the `{}` template:", kind.to_string())).await?;
+        },
+        "template reset" => |kind: enum TemplateKind| {
+            let kind = kind.context(here!())?;
+            let handle = config.get_database_handle()?;
+            EmbedTemplate::reset(&handle, interaction.guild_id.unwrap(), kind)?;
+
+            show_template(ctx, interaction, config, &EmbedTemplate::default_for(kind), &format!("Reset the `{}` template:", kind.to_string())).await?;
+        },
+        "digest set" => |time: req String, timezone: req String, branch: enum HoloBranch| {
+            let content = match (NaiveTime::parse_from_str(&time, "%H:%M"), chrono_tz::Tz::from_str(&timezone)) {
+                (Err(_), _) => format!("Could not understand the time '{}', use a 24-hour time such as `18:00`.", time),
+                (_, Err(_)) => format!("Could not find the timezone '{}', use a name such as `Europe/London`.", timezone),
+                (Ok(post_time), Ok(timezone)) => {
+                    let mut digest = ScheduleDigest {
+                        guild: interaction.guild_id.unwrap(),
+                        channel: interaction.channel_id.unwrap(),
+                        post_time,
+                        timezone,
+                        branch,
+                        message: None,
+                        posted_for: None,
+                    };
+
+                    // Start with the next digest rather than posting one for the current day.
+                    digest.posted_for = Some(digest.last_due(Utc::now()));
+                    digest.save(&config.get_database_handle()?)?;
+
+                    let next = digest.last_due(Utc::now()) + chrono::Duration::days(1);
+                    format!("The schedule digest will be posted in this channel every day, starting <t:{}:F>.", next.timestamp())
+                }
+            };
+
+            interaction.create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|d|
+                        d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .content(content))
+            }).await?;
+        },
+        "digest remove" => {
+            let removed = ScheduleDigest::remove(&config.get_database_handle()?, interaction.guild_id.unwrap())?;
+
+            let content = if removed {
+                "The schedule digest won't be posted anymore."
+            } else {
+                "This server doesn't have a schedule digest."
+            };
+
+            interaction.create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|d|
+                        d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .content(content))
+            }).await?;
         }
     };
 
     Ok(())
 }
+
+/// `-` removes the part, while leaving the option out keeps the current text.
+fn merge_template_part(current: Option<String>, new: Option<String>) -> Option<String> {
+    match new.as_deref() {
+        Some("-") => None,
+        Some(_) => new,
+        None => current,
+    }
+}
+
+/// Values used to fill in templates when showing or checking them.
+fn sample_values(config: &Config) -> HashMap<&'static str, String> {
+    let now = Utc::now();
+    let talent = config
+        .users
+        .first()
+        .map_or_else(|| "Talent".to_owned(), |u| u.display_name.clone());
+
+    let mut values = HashMap::new();
+    values.insert("talent", talent.clone());
+    values.insert("mention", format!("@{}", talent));
+    values.insert("title", "Sample stream title".to_owned());
+    values.insert("text", "Sample text of the post.".to_owned());
+    values.insert("url", "https://example.com".to_owned());
+    values.insert("time", format!("<t:{}:F>", now.timestamp()));
+    values.insert("relative_time", format!("<t:{}:R>", now.timestamp()));
+
+    values
+}
+
+/// Shows the template filled in with sample values, along with the available placeholders.
+async fn show_template(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+    template: &EmbedTemplate,
+    heading: &str,
+) -> anyhow::Result<()> {
+    let rendered = template.render(&sample_values(config));
+
+    let mut embed = CreateEmbed::default();
+    rendered.apply(&mut embed);
+
+    // Discord rejects embeds without any text.
+    let embeds = if rendered.title.is_some()
+        || rendered.description.is_some()
+        || rendered.footer.is_some()
+    {
+        vec![embed]
+    } else {
+        Vec::new()
+    };
+
+    let placeholders = EmbedTemplate::PLACEHOLDERS
+        .iter()
+        .map(|(name, desc)| format!("`{{{}}}`: {}", name, desc))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| {
+                    d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .content(format!(
+                            "{}\n{}\n\n**Placeholders**\n{}",
+                            heading,
+                            rendered.content.as_deref().unwrap_or(""),
+                            placeholders
+                        ))
+                        .embeds(embeds)
+                })
+        })
+        .await
+        .context(here!())?;
+
+    Ok(())
+}
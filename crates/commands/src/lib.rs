@@ -13,14 +13,20 @@ mod help;
 mod interactions;
 
 pub mod birthdays;
+pub mod components;
 pub mod config;
 pub mod eightball;
 pub mod emoji_usage;
 pub mod live;
+pub mod logs;
 pub mod meme;
 pub mod ogey;
 pub mod quote;
 /* pub mod reminder; */
+pub mod role_menu;
+pub mod subscribe;
+pub mod subscriptions;
+pub mod translate;
 pub mod upcoming;
 
 define_command_group!(Fun, [pekofy]);
@@ -0,0 +1,296 @@
+use std::borrow::Cow;
+
+use itertools::Itertools;
+use serenity::model::channel::AttachmentType;
+
+use super::prelude::*;
+
+use apis::discord_api::{render_html, render_markdown};
+use utility::config::{ChatArchive, UserCollection};
+
+/// Number of matches shown for a search.
+const SEARCH_RESULTS: usize = 10;
+/// Matched messages are cut off at this many characters, so the results fit in one embed.
+const PREVIEW_LENGTH: usize = 200;
+/// Discord's upload limit for servers without boosts.
+const ATTACHMENT_LIMIT: usize = 8 * 1024 * 1024;
+/// Discord's limit on the length of embed titles.
+const TITLE_LIMIT: usize = 256;
+
+interaction_setup! {
+    name = "logs",
+    group = "utility",
+    description = "Search the chats of archived streams.",
+    options = [
+        //! Find chat messages containing all the given words.
+        search: SubCommand = [
+            //! Words to look for.
+            req query: String,
+            //! Only search the chats of this talent's streams.
+            talent: String,
+            //! Only search the chat of this stream, by video ID or link.
+            stream: String,
+        ],
+        //! Get the whole chat of an archived stream as files.
+        stream: SubCommand = [
+            //! Video ID or link of the stream.
+            req id: String,
+        ],
+    ],
+    restrictions = [
+        rate_limit = 5 in 1 minute for user
+    ]
+}
+
+#[interaction_cmd]
+async fn logs(ctx: &Ctx, interaction: &Interaction, config: &Config) -> anyhow::Result<()> {
+    match_sub_commands! {
+        "search" => |query: req String, talent: String, stream: String| {
+            search_logs(ctx, interaction, config, &query, talent.as_deref(), stream.as_deref()).await?;
+        },
+        "stream" => |id: req String| {
+            show_log(ctx, interaction, &id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn search_logs(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+    query: &str,
+    talent: Option<&str>,
+    stream: Option<&str>,
+) -> anyhow::Result<()> {
+    show_deferred_response(&interaction, &ctx, true).await?;
+
+    if query.trim().is_empty() {
+        Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+            r.content("Give at least one word to search for.")
+        })
+        .await
+        .context(here!())?;
+
+        return Ok(());
+    }
+
+    let talent = match talent {
+        Some(name) => match config.users.find_by_name(name) {
+            Some(user) => Some(user.name.as_str()),
+            None => {
+                Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                    r.content(format!("Couldn't find a talent called '{}'.", name))
+                })
+                .await
+                .context(here!())?;
+
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let matches = {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        if ChatArchive::search_available(&handle)? {
+            Some(ChatArchive::search(
+                &handle,
+                query,
+                talent,
+                stream.map(video_id),
+                SEARCH_RESULTS,
+            )?)
+        } else {
+            None
+        }
+    };
+
+    let matches = match matches {
+        Some(m) => m,
+        None => {
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content("Searching the chats isn't available right now, sorry!")
+            })
+            .await
+            .context(here!())?;
+
+            return Ok(());
+        }
+    };
+
+    if matches.is_empty() {
+        Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+            r.content(format!("Couldn't find any messages matching '{}'.", query))
+        })
+        .await
+        .context(here!())?;
+
+        return Ok(());
+    }
+
+    let results = matches
+        .iter()
+        .map(|(archive, message)| {
+            let mut preview = message
+                .content
+                .chars()
+                .take(PREVIEW_LENGTH)
+                .collect::<String>();
+
+            if preview.len() < message.content.len() {
+                preview.push_str("...");
+            }
+
+            format!(
+                "`{}` **{}** {}\n{}: {}",
+                archive.id,
+                archive.title,
+                message.format_timestamp(),
+                message.author_name,
+                preview
+            )
+        })
+        .join("\n\n");
+
+    let title = format!("Messages matching '{}'", query)
+        .chars()
+        .take(TITLE_LIMIT)
+        .collect::<String>();
+
+    Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+        r.content("").create_embed(|e| {
+            e.title(title)
+                .description(results)
+                .footer(|f| f.text("Use /logs stream with the video ID to get the whole chat."))
+        })
+    })
+    .await
+    .context(here!())?;
+
+    Ok(())
+}
+
+async fn show_log(ctx: &Ctx, interaction: &Interaction, id: &str) -> anyhow::Result<()> {
+    show_deferred_response(&interaction, &ctx, true).await?;
+
+    let id = video_id(id);
+
+    let archive = {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        match ChatArchive::load(&handle, id)? {
+            Some(archive) => {
+                let messages = archive.messages(&handle)?;
+                Some((archive, messages))
+            }
+            None => None,
+        }
+    };
+
+    let (archive, messages) = match archive {
+        Some(a) => a,
+        None => {
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content(format!("Couldn't find an archived chat for '{}'.", id))
+            })
+            .await
+            .context(here!())?;
+
+            return Ok(());
+        }
+    };
+
+    let markdown = render_markdown(&archive, &messages);
+    let html = render_html(&archive, &messages);
+
+    if markdown.len() + html.len() > ATTACHMENT_LIMIT {
+        Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+            r.content("The chat is too large to upload, sorry!")
+        })
+        .await
+        .context(here!())?;
+
+        return Ok(());
+    }
+
+    Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+        r.content(format!(
+            "Chat of **{}**, {} messages.",
+            archive.title,
+            messages.len()
+        ))
+    })
+    .await
+    .context(here!())?;
+
+    // Only the member who asked gets the files, rather than the whole channel. Follow-ups
+    // are executed like webhooks, and serenity drops the files of its follow-up builder.
+    let mut payload = serde_json::Map::new();
+    payload.insert(
+        "flags".to_owned(),
+        InteractionApplicationCommandCallbackDataFlags::EPHEMERAL
+            .bits()
+            .into(),
+    );
+
+    ctx.http
+        .execute_webhook_with_files(
+            interaction.application_id.0,
+            &interaction.token,
+            true,
+            vec![
+                AttachmentType::Bytes {
+                    data: Cow::from(markdown.into_bytes()),
+                    filename: format!("{}-chat.md", archive.id),
+                },
+                AttachmentType::Bytes {
+                    data: Cow::from(html.into_bytes()),
+                    filename: format!("{}-chat.html", archive.id),
+                },
+            ],
+            payload,
+        )
+        .await
+        .context(here!())?;
+
+    Ok(())
+}
+
+/// Takes either a video ID or a YouTube link.
+fn video_id(input: &str) -> &str {
+    let input = input.trim();
+
+    match input.split_once("v=") {
+        Some((_, id)) => id.split('&').next().unwrap_or(id),
+        None => input
+            .rsplit('/')
+            .next()
+            .and_then(|id| id.split('?').next())
+            .unwrap_or(input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_video_ids_and_links() {
+        assert_eq!(video_id("dQw4w9WgXcQ"), "dQw4w9WgXcQ");
+        assert_eq!(video_id(" dQw4w9WgXcQ "), "dQw4w9WgXcQ");
+        assert_eq!(
+            video_id("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42"),
+            "dQw4w9WgXcQ"
+        );
+        assert_eq!(video_id("https://youtu.be/dQw4w9WgXcQ?t=42"), "dQw4w9WgXcQ");
+        assert_eq!(
+            video_id("https://www.youtube.com/live/dQw4w9WgXcQ"),
+            "dQw4w9WgXcQ"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use std::{collections::HashSet, convert::TryFrom};
+
+use serenity::{
+    builder::CreateComponents,
+    model::{channel::ReactionType, id::GuildId, interactions::InteractionData},
+};
+use strum::IntoEnumIterator;
+
+use super::prelude::*;
+
+use utility::config::{HoloBranch, HoloGeneration, LoadFromDatabase, RoleMenu, User};
+
+/// Prefix of the custom IDs of the role menu's select menus.
+pub const ROLE_MENU_SELECT: &str = "role_menu";
+
+/// Discord's limits on select menus per message and options per select menu.
+const MENUS_PER_MESSAGE: usize = 5;
+const OPTIONS_PER_MENU: usize = 25;
+
+interaction_setup! {
+    name = "rolemenu",
+    group = "utility",
+    description = "Posts a menu members can use to pick talent roles.",
+    restrictions = [
+        allowed_roles = [
+            "Admin"
+        ]
+    ]
+}
+
+#[interaction_cmd]
+pub async fn rolemenu(ctx: &Ctx, interaction: &Interaction, config: &Config) -> anyhow::Result<()> {
+    show_deferred_response(&interaction, &ctx, true).await?;
+
+    let channel = interaction.channel_id.unwrap();
+    let guild = interaction.guild_id.unwrap();
+
+    let previous = {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        RoleMenu::load_from_database(&handle)?
+            .into_iter()
+            .find(|m| m.channel == channel)
+    };
+
+    let menu = update_role_menu(ctx, config, channel, guild, previous).await?;
+
+    Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+        r.content(format!(
+            "Posted a role menu with {} message(s). It will update itself when the roster changes.",
+            menu.messages.len()
+        ))
+    })
+    .await
+    .context(here!())?;
+
+    Ok(())
+}
+
+/// Regenerates the guild's role menus if the roster has changed since they were posted.
+#[instrument(skip(ctx, config))]
+pub async fn refresh_role_menus(ctx: &Ctx, config: &Config, guild: GuildId) -> anyhow::Result<()> {
+    let roster = RoleMenu::roster_signature(&config.users);
+
+    let outdated = {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        RoleMenu::load_from_database(&handle)?
+            .into_iter()
+            .filter(|m| m.guild == guild && m.roster != roster)
+            .collect::<Vec<_>>()
+    };
+
+    for menu in outdated {
+        info!(channel = %menu.channel, "Roster changed, regenerating role menu.");
+        update_role_menu(ctx, config, menu.channel, guild, Some(menu)).await?;
+    }
+
+    Ok(())
+}
+
+/// Toggles the role of the talent picked in a role menu.
+pub async fn toggle_roles(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let values = match &interaction.data {
+        Some(InteractionData::MessageComponent(d)) => &d.values,
+        _ => return Ok(()),
+    };
+
+    let mut member = interaction
+        .member
+        .clone()
+        .ok_or_else(|| anyhow!("Role menu used outside of a guild."))
+        .context(here!())?;
+
+    // Only roster roles can be handed out, whatever the menu claims.
+    let roster_roles = config
+        .users
+        .iter()
+        .map(|u| RoleId(u.discord_role))
+        .collect::<HashSet<_>>();
+
+    let (removed, added): (Vec<_>, Vec<_>) = values
+        .iter()
+        .filter_map(|v| v.parse().ok().map(RoleId))
+        .filter(|r| roster_roles.contains(r))
+        .partition(|r| member.roles.contains(r));
+
+    if !added.is_empty() {
+        member.add_roles(&ctx.http, &added).await.context(here!())?;
+    }
+
+    if !removed.is_empty() {
+        member
+            .remove_roles(&ctx.http, &removed)
+            .await
+            .context(here!())?;
+    }
+
+    let list = |roles: &[RoleId]| {
+        roles
+            .iter()
+            .map(|r| Mention::from(*r).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut lines = Vec::new();
+
+    if !added.is_empty() {
+        lines.push(format!("Added {}.", list(&added)));
+    }
+
+    if !removed.is_empty() {
+        lines.push(format!("Removed {}.", list(&removed)));
+    }
+
+    if lines.is_empty() {
+        lines.push("Nothing changed.".to_owned());
+    }
+
+    Interaction::create_interaction_response(interaction, &ctx.http, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|d| {
+                d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                    .content(lines.join("\n"))
+            })
+    })
+    .await
+    .context(here!())?;
+
+    Ok(())
+}
+
+/// Posts the role menu in the channel, reusing the messages of the previous one where possible.
+async fn update_role_menu(
+    ctx: &Ctx,
+    config: &Config,
+    channel: ChannelId,
+    guild: GuildId,
+    previous: Option<RoleMenu>,
+) -> anyhow::Result<RoleMenu> {
+    let pages = menu_pages(&config.users);
+    let mut old_messages = previous.map(|m| m.messages).unwrap_or_default().into_iter();
+    let mut messages = Vec::with_capacity(pages.len());
+
+    for (title, menus) in &pages {
+        let edited = match old_messages.next() {
+            Some(id) => channel
+                .edit_message(&ctx.http, id, |m| {
+                    m.content(title).components(|c| create_menus(c, menus))
+                })
+                .await
+                .map_err(|e| debug!("Couldn't reuse role menu message: {:?}", e))
+                .ok(),
+            None => None,
+        };
+
+        let message = match edited {
+            Some(m) => m,
+            None => channel
+                .send_message(&ctx.http, |m| {
+                    m.content(title).components(|c| create_menus(c, menus))
+                })
+                .await
+                .context(here!())?,
+        };
+
+        messages.push(message.id);
+    }
+
+    for leftover in old_messages {
+        if let Err(e) = channel.delete_message(&ctx.http, leftover).await {
+            debug!("{:?}", e);
+        }
+    }
+
+    let menu = RoleMenu {
+        channel,
+        guild,
+        messages,
+        roster: RoleMenu::roster_signature(&config.users),
+    };
+
+    {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        menu.save(&handle)?;
+    }
+
+    Ok(menu)
+}
+
+/// Splits the roster into messages per branch, with a select menu per generation.
+fn menu_pages(users: &[User]) -> Vec<(String, Vec<(String, Vec<&User>)>)> {
+    let mut pages = Vec::new();
+
+    for branch in HoloBranch::iter() {
+        let mut generations: Vec<(HoloGeneration, Vec<&User>)> = Vec::new();
+
+        for user in users.iter().filter(|u| u.branch == branch) {
+            match generations.iter_mut().find(|(g, _)| *g == user.generation) {
+                Some((_, members)) => members.push(user),
+                None => generations.push((user.generation, vec![user])),
+            }
+        }
+
+        let menus = generations
+            .iter()
+            .flat_map(|(generation, members)| {
+                let chunks = members.chunks(OPTIONS_PER_MENU).collect::<Vec<_>>();
+                let count = chunks.len();
+
+                chunks.into_iter().enumerate().map(move |(i, chunk)| {
+                    let name = if count > 1 {
+                        format!("{} ({}/{})", generation.to_string(), i + 1, count)
+                    } else {
+                        generation.to_string()
+                    };
+
+                    (name, chunk.to_vec())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (i, chunk) in menus.chunks(MENUS_PER_MESSAGE).enumerate() {
+            let title = if i == 0 {
+                format!(
+                    "**{}**\nPick a talent to get or remove their role.",
+                    branch.to_string()
+                )
+            } else {
+                format!("**{}** (continued)", branch.to_string())
+            };
+
+            pages.push((title, chunk.to_vec()));
+        }
+    }
+
+    pages
+}
+
+fn create_menus<'a>(
+    components: &'a mut CreateComponents,
+    menus: &[(String, Vec<&User>)],
+) -> &'a mut CreateComponents {
+    for (i, (name, members)) in menus.iter().enumerate() {
+        components.create_action_row(|row| {
+            row.create_select_menu(|menu| {
+                menu.custom_id(format!("{}:{}", ROLE_MENU_SELECT, i))
+                    .placeholder(name)
+                    // Each pick toggles a role, so a single pick keeps earlier picks
+                    // from being sent again and undone.
+                    .min_values(1)
+                    .max_values(1)
+                    .options(|options| {
+                        for user in members {
+                            options.create_option(|o| {
+                                o.label(&user.display_name)
+                                    .value(user.discord_role.to_string());
+
+                                if !user.emoji.is_empty() {
+                                    if let Ok(emoji) = ReactionType::try_from(user.emoji.as_str()) {
+                                        o.emoji(emoji);
+                                    }
+                                }
+
+                                o
+                            });
+                        }
+
+                        options
+                    })
+            })
+        });
+    }
+
+    components
+}
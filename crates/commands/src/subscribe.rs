@@ -0,0 +1,103 @@
+use std::str::FromStr;
+
+use strum::IntoEnumIterator;
+
+use super::prelude::*;
+
+use utility::config::{Subscription, SubscriptionEvent, UserCollection};
+
+interaction_setup! {
+    name = "subscribe",
+    group = "utility",
+    description = "Get DMs when a talent goes live, tweets and more.",
+    options = [
+        //! The talent to get DMs about.
+        req talent: String,
+        //! Comma-separated list of live, schedule, tweet and birthday. Defaults to all of them.
+        events: String,
+    ],
+    restrictions = [
+        rate_limit = 10 in 1 minute for user
+    ]
+}
+
+#[interaction_cmd]
+pub async fn subscribe(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    parse_interaction_options!(
+        interaction.data.as_ref().unwrap(), [
+        talent: req String,
+        events: String,
+    ]);
+
+    show_deferred_response(&interaction, &ctx, true).await?;
+
+    let talent = match config.users.find_by_name(&talent) {
+        Some(t) => t,
+        None => {
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content(format!("Couldn't find a talent called '{}'.", talent))
+            })
+            .await
+            .context(here!())?;
+
+            return Ok(());
+        }
+    };
+
+    let events = match events {
+        Some(events) => events
+            .split(',')
+            .map(|e| SubscriptionEvent::from_str(&e.trim().to_lowercase()).map_err(|_| e.trim()))
+            .collect::<Result<Vec<_>, _>>(),
+        None => Ok(SubscriptionEvent::iter().collect()),
+    };
+
+    let events = match events {
+        Ok(events) => events,
+        Err(unknown) => {
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content(format!(
+                    "'{}' isn't something you can subscribe to, try one of: {}.",
+                    unknown,
+                    SubscriptionEvent::iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            })
+            .await
+            .context(here!())?;
+
+            return Ok(());
+        }
+    };
+
+    let user = interaction.member.as_ref().unwrap().user.id;
+
+    {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        Subscription::subscribe(&handle, user, &talent.name, &events)?;
+    }
+
+    Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+        r.content(format!(
+            "You'll get DMs about {} from {}. Make sure you allow DMs from server members!",
+            events
+                .iter()
+                .map(|e| format!("`{}`", e.to_string()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            talent.display_name
+        ))
+    })
+    .await
+    .context(here!())?;
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use serenity::model::id::UserId;
+
+use super::prelude::*;
+
+use utility::config::{Subscription, SubscriptionEvent, UserCollection};
+
+interaction_setup! {
+    name = "subscriptions",
+    group = "utility",
+    description = "Manage the DMs you get about talents.",
+    options = [
+        //! Show what you're subscribed to.
+        list: SubCommand,
+        //! Stop getting DMs about a talent.
+        remove: SubCommand = [
+            //! The talent to stop getting DMs about.
+            req talent: String,
+            //! Only stop getting DMs about this, instead of everything from the talent.
+            event: String = enum SubscriptionEvent,
+        ],
+    ]
+}
+
+#[interaction_cmd]
+async fn subscriptions(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let user = interaction.member.as_ref().unwrap().user.id;
+
+    match_sub_commands! {
+        "list" => {
+            list_subscriptions(ctx, interaction, config, user).await?;
+        },
+        "remove" => |talent: req String, event: enum SubscriptionEvent| {
+            remove_subscriptions(ctx, interaction, config, user, &talent, event).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn list_subscriptions(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+    user: UserId,
+) -> anyhow::Result<()> {
+    let subscriptions = {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        Subscription::for_user(&handle, user)?
+    };
+
+    let content = if subscriptions.is_empty() {
+        "You aren't subscribed to anyone, use `/subscribe` to get started!".to_owned()
+    } else {
+        let mut by_talent: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+
+        for subscription in &subscriptions {
+            by_talent
+                .entry(&subscription.talent)
+                .or_default()
+                .push(subscription.event.to_string());
+        }
+
+        by_talent
+            .into_iter()
+            .map(|(talent, events)| {
+                let name = config
+                    .users
+                    .iter()
+                    .find(|u| u.name == talent)
+                    .map_or(talent, |u| u.display_name.as_str());
+
+                format!("**{}**: {}", name, events.join(", "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| {
+                    d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .content(content)
+                })
+        })
+        .await
+        .context(here!())?;
+
+    Ok(())
+}
+
+async fn remove_subscriptions(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+    user: UserId,
+    talent: &str,
+    event: Option<SubscriptionEvent>,
+) -> anyhow::Result<()> {
+    // Talents that have left the roster can still be unsubscribed from by name.
+    let (name, display_name) = match config.users.find_by_name(talent) {
+        Some(u) => (u.name.as_str(), u.display_name.as_str()),
+        None => (talent, talent),
+    };
+
+    let removed = {
+        let data = ctx.data.read().await;
+        let handle = data.get::<DbHandle>().unwrap().lock().await;
+
+        Subscription::unsubscribe(&handle, user, name, event)?
+    };
+
+    let content = match (removed, event) {
+        (0, _) => format!("You weren't subscribed to {}.", display_name),
+        (_, Some(event)) => format!(
+            "You won't get DMs about `{}` from {} anymore.",
+            event.to_string(),
+            display_name
+        ),
+        (_, None) => format!("You won't get DMs from {} anymore.", display_name),
+    };
+
+    interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| {
+                    d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .content(content)
+                })
+        })
+        .await
+        .context(here!())?;
+
+    Ok(())
+}
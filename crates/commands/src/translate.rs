@@ -0,0 +1,437 @@
+use std::str::FromStr;
+
+use regex::Regex;
+
+use super::prelude::*;
+
+use apis::{
+    translation_api::{detect_language, TranslationApi, TranslatorType, TRANSLATION_VOTE_BUTTON},
+    twitter_api::TwitterApi,
+};
+use serenity::model::interactions::ButtonStyle;
+use utility::regex;
+
+interaction_setup! {
+    name = "translate",
+    group = "utility",
+    description = "Translates text or a linked message.",
+    options = [
+        //! The text to translate, or a link to the message to translate.
+        req text: String,
+        //! The language to translate from, detected automatically if not set.
+        from: String = [
+            "Japanese": "ja",
+            "Indonesian": "id",
+            "Korean": "ko",
+            "Chinese": "zh",
+            "German": "de",
+            "Spanish": "es",
+            "French": "fr",
+        ],
+        //! The language to translate to, defaults to the channel's language.
+        to: String = [
+            "English": "en",
+            "Japanese": "ja",
+            "Indonesian": "id",
+            "Korean": "ko",
+            "Chinese": "zh",
+            "German": "de",
+            "Spanish": "es",
+            "French": "fr",
+        ],
+        //! Which translator to use.
+        engine: String = enum TranslatorType,
+        //! Show the translations of every translator side by side.
+        compare: Boolean,
+    ],
+    restrictions = [
+        rate_limit = 5 in 1 minute for user
+    ]
+}
+
+#[interaction_cmd]
+pub async fn translate(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+) -> anyhow::Result<()> {
+    parse_interaction_options!(
+        interaction.data.as_ref().unwrap(), [
+        text: req String,
+        from: String,
+        to: String,
+        engine: enum TranslatorType,
+        compare: bool = false,
+    ]);
+
+    show_deferred_response(&interaction, &ctx, true).await?;
+
+    let text = match resolve_message_link(ctx, interaction, &text).await {
+        Some(Ok(content)) => content,
+        Some(Err(reason)) => {
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content(reason)
+            })
+            .await
+            .context(here!())?;
+
+            return Ok(());
+        }
+        None => text,
+    };
+
+    if text.trim().is_empty() {
+        Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+            r.content("There is no text to translate.")
+        })
+        .await
+        .context(here!())?;
+
+        return Ok(());
+    }
+
+    let from = match from.as_deref().or_else(|| detect_language(&text)) {
+        Some(lang) => lang.to_owned(),
+        None => {
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content("Couldn't detect the language, please specify it with `from`.")
+            })
+            .await
+            .context(here!())?;
+
+            return Ok(());
+        }
+    };
+
+    let to = match to {
+        Some(to) => to,
+        None => config
+            .translation
+            .policy_for(interaction.channel_id.unwrap(), interaction.guild_id)
+            .target
+            .clone(),
+    };
+
+    if from == to {
+        Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+            r.content("The text is already in the target language.")
+        })
+        .await
+        .context(here!())?;
+
+        return Ok(());
+    }
+
+    let translator = {
+        let data = ctx.data.read().await;
+        data.get::<TranslationApi>().unwrap().clone()
+    };
+
+    if compare {
+        return show_comparison(
+            ctx,
+            interaction,
+            &translator,
+            &text,
+            &from,
+            &to,
+            &interaction.id.to_string(),
+        )
+        .await;
+    }
+
+    let translation = match engine {
+        Some(engine) => translator.translate_with(engine, &text, &from, &to).await,
+        None => translator.translate(&text, &from, &to).await,
+    };
+
+    match translation {
+        Ok(translation) => {
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.create_embed(|e| {
+                    e.description(translation);
+                    e.footer(|f| {
+                        f.text(match engine {
+                            Some(engine) => {
+                                format!(
+                                    "Translated from {} to {} by {}",
+                                    from,
+                                    to,
+                                    engine.to_string()
+                                )
+                            }
+                            None => format!("Translated from {} to {}", from, to),
+                        })
+                    })
+                })
+            })
+            .await
+            .context(here!())?;
+        }
+        Err(err) => {
+            warn!("{:?}", err);
+
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content("Sorry, the text couldn't be translated.")
+            })
+            .await
+            .context(here!())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Compares the translations of the tweet the button is attached to.
+pub async fn compare_tweet(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    config: &Config,
+    tweet_id: &str,
+) -> anyhow::Result<()> {
+    let is_moderator = interaction
+        .member
+        .as_ref()
+        .and_then(|m| m.permissions)
+        .map_or(false, |p| p.manage_messages());
+
+    if !is_moderator {
+        Interaction::create_interaction_response(interaction, &ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| {
+                    d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                        .content("Only moderators can compare translations.")
+                })
+        })
+        .await
+        .context(here!())?;
+
+        return Ok(());
+    }
+
+    show_deferred_response(&interaction, &ctx, true).await?;
+
+    let tweet_id = tweet_id.parse::<u64>().context(here!())?;
+    let (text, lang) = TwitterApi::get_tweet_text(config, tweet_id).await?;
+
+    let lang = match lang {
+        Some(lang) => lang,
+        None => {
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content("Couldn't detect the language of the tweet.")
+            })
+            .await
+            .context(here!())?;
+
+            return Ok(());
+        }
+    };
+
+    let to = &config
+        .translation
+        .policy_for(interaction.channel_id.unwrap(), interaction.guild_id)
+        .target;
+
+    let translator = {
+        let data = ctx.data.read().await;
+        data.get::<TranslationApi>().unwrap().clone()
+    };
+
+    show_comparison(
+        ctx,
+        interaction,
+        &translator,
+        &text,
+        &lang,
+        to,
+        &format!("tweet-{}", tweet_id),
+    )
+    .await
+}
+
+/// Records the translation a user picked as the best one in a comparison.
+pub async fn record_vote(ctx: &Ctx, interaction: &Interaction, args: &str) -> anyhow::Result<()> {
+    let mut parts = args.rsplitn(3, ':');
+
+    let (translator_type, lang, source_id) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(t), Some(l), Some(s)) => (TranslatorType::from_str(t).context(here!())?, l, s),
+        _ => return Err(anyhow!("Invalid vote: '{}'.", args).context(here!())),
+    };
+
+    let user = interaction
+        .member
+        .as_ref()
+        .map(|m| m.user.id)
+        .ok_or_else(|| anyhow!("Vote has no member."))
+        .context(here!())?;
+
+    let translator = {
+        let data = ctx.data.read().await;
+        data.get::<TranslationApi>().unwrap().clone()
+    };
+
+    translator.record_vote(source_id, *user.as_u64(), lang, translator_type)?;
+
+    Interaction::create_interaction_response(interaction, &ctx.http, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|d| {
+                d.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL)
+                    .content(format!(
+                        "Thanks, your vote for {} was recorded!",
+                        translator_type.to_string()
+                    ))
+            })
+    })
+    .await
+    .context(here!())?;
+
+    Ok(())
+}
+
+async fn show_comparison(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    translator: &TranslationApi,
+    text: &str,
+    from: &str,
+    to: &str,
+    source_id: &str,
+) -> anyhow::Result<()> {
+    let results = match translator.compare(text, from, to).await {
+        Ok(results) => results,
+        Err(err) => {
+            warn!("{:?}", err);
+
+            Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+                r.content("Sorry, the text couldn't be translated.")
+            })
+            .await
+            .context(here!())?;
+
+            return Ok(());
+        }
+    };
+
+    let votes = translator.vote_counts(from).unwrap_or_default();
+
+    Interaction::edit_original_interaction_response(interaction, &ctx.http, |r| {
+        r.create_embed(|e| {
+            e.title("Translation comparison");
+            e.footer(|f| f.text(format!("Translated from {} to {}", from, to)));
+
+            for (translator_type, result) in &results {
+                let value = match result {
+                    Ok(translation) => truncate(translation, 1024),
+                    Err(err) => {
+                        debug!("{:?}", err);
+                        "*Translation failed.*".to_owned()
+                    }
+                };
+
+                e.field(
+                    format!(
+                        "{} ({} votes)",
+                        translator_type.to_string(),
+                        votes.get(translator_type).unwrap_or(&0)
+                    ),
+                    value,
+                    false,
+                );
+            }
+
+            e
+        });
+
+        // Discord rejects empty action rows.
+        if results.iter().any(|(_, r)| r.is_ok()) {
+            r.components(|c| {
+                c.create_action_row(|row| {
+                    for (translator_type, _) in results.iter().filter(|(_, r)| r.is_ok()) {
+                        row.create_button(|b| {
+                            b.style(ButtonStyle::Secondary)
+                                .label(format!("{} is best", translator_type.to_string()))
+                                .custom_id(format!(
+                                    "{}:{}:{}:{}",
+                                    TRANSLATION_VOTE_BUTTON,
+                                    source_id,
+                                    from,
+                                    translator_type.to_string()
+                                ))
+                        });
+                    }
+
+                    row
+                })
+            });
+        }
+
+        r
+    })
+    .await
+    .context(here!())?;
+
+    Ok(())
+}
+
+fn truncate(text: &str, max_len: usize) -> String {
+    if text.chars().count() <= max_len {
+        text.to_owned()
+    } else {
+        format!("{}...", text.chars().take(max_len - 3).collect::<String>())
+    }
+}
+
+/// Returns the content of the linked message, if the text is a message link. Only messages
+/// from this server that the member can read themselves are resolved.
+// TODO: Add the "Translate message" context-menu action once serenity is updated to 0.10.10,
+// the first release that exposes the target of message commands. That update reshapes
+// `Interaction` and needs its own change, so links stand in for it until then.
+async fn resolve_message_link(
+    ctx: &Ctx,
+    interaction: &Interaction,
+    text: &str,
+) -> Option<Result<String, &'static str>> {
+    let link_rgx: &'static Regex = regex!(
+        r"^https://(?:\w+\.)?discord(?:app)?\.com/channels/(?P<guild>\d+)/(?P<channel>\d+)/(?P<message>\d+)$"
+    );
+
+    let captures = link_rgx.captures(text.trim())?;
+    let guild = captures.name("guild")?.as_str().parse::<u64>().ok()?;
+    let channel = captures.name("channel")?.as_str().parse().ok()?;
+    let message = captures.name("message")?.as_str().parse().ok()?;
+
+    if interaction.guild_id.map(|g| g.0) != Some(guild) {
+        return Some(Err("I can only translate messages from this server."));
+    }
+
+    let member = match &interaction.member {
+        Some(member) => member.user.id,
+        None => return Some(Err("I can only translate messages from this server.")),
+    };
+
+    let channel = match ctx.cache.guild_channel(ChannelId(channel)).await {
+        Some(channel) if channel.guild_id.0 == guild => channel,
+        _ => return Some(Err("Couldn't find that channel.")),
+    };
+
+    // `READ_MESSAGES` is what Discord calls the View Channel permission.
+    let can_read = match channel.permissions_for_user(&ctx.cache, member).await {
+        Ok(permissions) => permissions.read_messages() && permissions.read_message_history(),
+        Err(err) => {
+            debug!("{:?}", err);
+            false
+        }
+    };
+
+    if !can_read {
+        return Some(Err("You can't read messages in that channel."));
+    }
+
+    match channel.id.message(&ctx.http, MessageId(message)).await {
+        Ok(message) => Some(Ok(message.content)),
+        Err(err) => {
+            debug!("{:?}", err);
+            Some(Err("Couldn't find that message."))
+        }
+    }
+}
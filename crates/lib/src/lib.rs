@@ -45,6 +45,8 @@
     clippy::multiple_crate_versions
 )]
 
+use std::sync::Arc;
+
 use futures::stream::StreamExt;
 use signal_hook::consts::signal::{SIGHUP, SIGINT, SIGQUIT, SIGTERM};
 use signal_hook_tokio::Signals;
@@ -56,6 +58,7 @@ use apis::{
     discord_api::{DiscordApi, DiscordMessageData},
     holo_api::{HoloApi, StreamUpdate},
     /* reminder_notifier::ReminderNotifier, */
+    translation_api::TranslationApi,
     twitter_api::TwitterApi,
 };
 use bot::DiscordBot;
@@ -101,11 +104,17 @@ impl HoloBot {
 
         let config = Config::load_config(Self::get_config_path())?;
 
+        // Shared, so the translator health, quota and cache are the same everywhere.
+        let translator = Arc::new(TranslationApi::new(&config)?);
+
         let (discord_message_tx, discord_message_rx): (
             mpsc::Sender<DiscordMessageData>,
             mpsc::Receiver<DiscordMessageData>,
         ) = mpsc::channel(10);
 
+        // Tweets are translated before they're handed to the posting thread.
+        let (tweet_tx, tweet_rx) = mpsc::channel(10);
+
         let (stream_update_tx, stream_update_rx): (
             broadcast::Sender<StreamUpdate>,
             broadcast::Receiver<StreamUpdate>,
@@ -123,12 +132,7 @@ impl HoloBot {
         )
         .await;
 
-        TwitterApi::start(
-            config.clone(),
-            discord_message_tx.clone(),
-            exit_receiver.clone(),
-        )
-        .await;
+        TwitterApi::start(config.clone(), tweet_tx, exit_receiver.clone()).await;
 
         BirthdayReminder::start(
             config.clone(),
@@ -150,6 +154,7 @@ impl HoloBot {
             stream_update_tx.clone(),
             reminder_update_rx,
             index_receiver.clone(),
+            Arc::clone(&translator),
             guild_ready_tx,
             exit_receiver.clone(),
         )
@@ -158,7 +163,10 @@ impl HoloBot {
         DiscordApi::start(
             cache,
             config.clone(),
+            translator,
             discord_message_rx,
+            discord_message_tx,
+            tweet_rx,
             stream_update_rx,
             index_receiver,
             guild_ready_rx,
@@ -11,17 +11,22 @@ use chrono::prelude::*;
 /* use parking_lot::{Mutex, MutexGuard}; */
 use regex::Regex;
 use rusqlite::{
+    params,
     types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, Value, ValueRef},
-    Connection, ToSql,
+    Connection, OptionalExtension, ToSql,
 };
 use serde::{Deserialize, Serialize};
 use serde_hex::{SerHex, StrictPfx};
 use serenity::{
     builder::CreateEmbed,
-    model::id::{ChannelId, UserId},
+    model::{
+        id::{ChannelId, GuildId, MessageId, UserId},
+        misc::Mention,
+    },
     prelude::TypeMapKey,
 };
 use strum_macros::{EnumIter, EnumString, ToString};
+use tracing::warn;
 use url::Url;
 
 use crate::{here, regex};
@@ -81,207 +86,1597 @@ pub struct Config {
 
     pub stream_chat_category: u64,
     pub stream_chat_logs: u64,
+    #[serde(default)]
+    pub chat_log_export: ChatLogExport,
 
     #[serde(default = "bool::default")]
     pub development: bool,
     #[serde(default = "HashSet::new")]
     pub blocked_servers: HashSet<u64>,
 
+    #[serde(default = "HashMap::new")]
     pub twitter_feeds: HashMap<HoloBranch, HashMap<HoloGeneration, u64>>,
+    #[serde(default = "Vec::new")]
+    pub twitter_routes: Vec<TweetRoute>,
+    #[serde(default = "bool::default")]
+    pub quote_cross_channel_replies: bool,
+    #[serde(default)]
+    pub fanart: Option<FanartConfig>,
+    #[serde(default)]
+    pub translation: TranslationConfig,
+    /// Channels where tweets are posted through a webhook, under the talent's name and avatar.
+    #[serde(default = "HashSet::new")]
+    pub webhook_channels: HashSet<u64>,
+    /// Channel with a pinned message listing who is live, which the bot keeps up to date.
+    #[serde(default)]
+    pub live_dashboard_channel: Option<u64>,
+    #[serde(default)]
+    pub scheduled_events: Option<ScheduledEventsConfig>,
 
     #[serde(skip)]
     pub users: Vec<User>,
 }
 
-impl Config {
-    pub fn load_config(path: &str) -> anyhow::Result<Self> {
-        let config_json = fs::read_to_string(path).context(here!())?;
-        let mut config: Self = serde_json::from_str(&config_json).context(here!())?;
+impl Config {
+    pub fn load_config(path: &str) -> anyhow::Result<Self> {
+        let config_json = fs::read_to_string(path).context(here!())?;
+        let mut config: Self = serde_json::from_str(&config_json).context(here!())?;
+
+        let db_handle = Connection::open(&config.database_path).context(here!())?;
+
+        Self::initialize_tables(&db_handle)?;
+
+        config.users = User::load_from_database(&db_handle)?;
+
+        // Keep old branch/generation feeds working by turning them into routes, in a stable order.
+        let mut legacy_feeds = config
+            .twitter_feeds
+            .iter()
+            .flat_map(|(branch, generations)| {
+                generations
+                    .iter()
+                    .map(move |(generation, channel)| (*branch, *generation, *channel))
+            })
+            .collect::<Vec<_>>();
+
+        legacy_feeds.sort_unstable();
+
+        let legacy_routes = legacy_feeds
+            .into_iter()
+            .map(|(branch, generation, channel)| TweetRoute {
+                channel,
+                filter: TweetFilter {
+                    branches: Some(vec![branch]),
+                    generations: Some(vec![generation]),
+                    ..TweetFilter::default()
+                },
+                stop: false,
+            })
+            .collect::<Vec<_>>();
+
+        config.twitter_routes.extend(legacy_routes);
+
+        Ok(config)
+    }
+
+    /// Returns every channel a tweet should be posted in, in the order the routes were declared.
+    #[must_use]
+    pub fn route_tweet(&self, tweet: &TweetProperties<'_>) -> Vec<ChannelId> {
+        let mut channels = Vec::new();
+
+        for route in &self.twitter_routes {
+            if !route.filter.matches(tweet) {
+                continue;
+            }
+
+            let channel = ChannelId(route.channel);
+
+            if !channels.contains(&channel) {
+                channels.push(channel);
+            }
+
+            if route.stop {
+                break;
+            }
+        }
+
+        channels
+    }
+
+    fn initialize_tables(handle: &Connection) -> anyhow::Result<()> {
+        handle.execute("CREATE TABLE IF NOT EXISTS emoji_usage (emoji_id INTEGER PRIMARY KEY, text_count INTEGER NOT NULL, reaction_count INTEGER NOT NULL)", []).context(here!())?;
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS Quotes (quote BLOB NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS Reminders (reminder BLOB NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS TranslationCache (text_hash INTEGER NOT NULL, source_text TEXT NOT NULL, source_lang TEXT NOT NULL, target_lang TEXT NOT NULL, translator TEXT NOT NULL, translation TEXT NOT NULL, created_at TEXT NOT NULL, PRIMARY KEY (text_hash, source_text, source_lang, target_lang, translator))",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS TranslatedTweets (message_id INTEGER PRIMARY KEY, original TEXT NOT NULL, translation TEXT NOT NULL, posted_at TEXT NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS TranslationVotes (source_id TEXT NOT NULL, user_id INTEGER NOT NULL, source_lang TEXT NOT NULL, translator TEXT NOT NULL, voted_at TEXT NOT NULL, PRIMARY KEY (source_id, user_id))",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS GlossaryTerms (term TEXT PRIMARY KEY, translation TEXT NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS DiscordOutbox (id INTEGER PRIMARY KEY AUTOINCREMENT, channel_id INTEGER NOT NULL, payload TEXT NOT NULL, attempts INTEGER NOT NULL, next_attempt_at TEXT NOT NULL, created_at TEXT NOT NULL, delivered_at TEXT, message_id INTEGER, failed_at TEXT, last_error TEXT)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS EmbedTemplates (guild_id INTEGER NOT NULL, kind TEXT NOT NULL, content TEXT, title TEXT, description TEXT, footer TEXT, PRIMARY KEY (guild_id, kind))",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS Subscriptions (user_id INTEGER NOT NULL, talent TEXT NOT NULL, event TEXT NOT NULL, subscribed_at TEXT NOT NULL, PRIMARY KEY (user_id, talent, event))",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS RoleMenus (channel_id INTEGER PRIMARY KEY, guild_id INTEGER NOT NULL, message_ids TEXT NOT NULL, roster TEXT NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS LiveNotifications (video_id TEXT PRIMARY KEY, channel_id INTEGER NOT NULL, message_id INTEGER NOT NULL, posted_at TEXT NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS NotifiedSpaces (space_id TEXT PRIMARY KEY, live INTEGER NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS ScheduleDigests (guild_id INTEGER PRIMARY KEY, channel_id INTEGER NOT NULL, post_time TEXT NOT NULL, timezone TEXT NOT NULL, branch TEXT, message_id INTEGER, posted_for TEXT)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS StreamEvents (video_id TEXT NOT NULL, guild_id INTEGER NOT NULL, event_id INTEGER NOT NULL, started INTEGER NOT NULL, PRIMARY KEY (video_id, guild_id))",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS ChatArchives (id TEXT PRIMARY KEY, video_id TEXT, title TEXT NOT NULL, talent TEXT, started_at TEXT NOT NULL, archived_at TEXT NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TABLE IF NOT EXISTS ChatMessages (id INTEGER PRIMARY KEY, archive_id TEXT NOT NULL, author_id INTEGER NOT NULL, author_name TEXT NOT NULL, content TEXT NOT NULL, offset_seconds INTEGER NOT NULL, attachments TEXT NOT NULL)",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE INDEX IF NOT EXISTS ChatMessagesByArchive ON ChatMessages (archive_id)",
+                [],
+            )
+            .context(here!())?;
+
+        // Archived chats can still be kept without search if SQLite was built without FTS5.
+        if let Err(e) = Self::initialize_chat_search(handle) {
+            warn!(
+                ?e,
+                "Couldn't set up chat log search, searching will be disabled."
+            );
+        }
+
+        Self::add_column_if_missing(handle, "DiscordOutbox", "webhook_name", "TEXT")?;
+        Self::add_column_if_missing(handle, "DiscordOutbox", "webhook_avatar", "TEXT")?;
+        Self::add_column_if_missing(handle, "DiscordOutbox", "delivery", "TEXT")?;
+        Self::add_column_if_missing(handle, "users", "fanart_hashtag", "TEXT")?;
+        Self::add_column_if_missing(handle, "users", "aliases", "TEXT")?;
+
+        Ok(())
+    }
+
+    /// Creates the full-text index of archived chat messages, which needs SQLite's FTS5 extension.
+    fn initialize_chat_search(handle: &Connection) -> anyhow::Result<()> {
+        handle
+            .execute(
+                "CREATE VIRTUAL TABLE IF NOT EXISTS ChatMessagesSearch USING fts5(content, content='ChatMessages', content_rowid='id')",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TRIGGER IF NOT EXISTS ChatMessagesInsert AFTER INSERT ON ChatMessages BEGIN INSERT INTO ChatMessagesSearch (rowid, content) VALUES (new.id, new.content); END",
+                [],
+            )
+            .context(here!())?;
+
+        handle
+            .execute(
+                "CREATE TRIGGER IF NOT EXISTS ChatMessagesDelete AFTER DELETE ON ChatMessages BEGIN INSERT INTO ChatMessagesSearch (ChatMessagesSearch, rowid, content) VALUES ('delete', old.id, old.content); END",
+                [],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    fn add_column_if_missing(
+        handle: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> anyhow::Result<()> {
+        let mut stmt = handle
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .context(here!())?;
+
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>("name"))
+            .context(here!())?
+            .collect::<Result<Vec<_>, _>>()
+            .context(here!())?;
+
+        if !columns.iter().any(|c| c == column) {
+            handle
+                .execute(
+                    &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                    [],
+                )
+                .context(here!())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_database_handle(&self) -> anyhow::Result<Connection> {
+        Connection::open(&self.database_path).context(here!())
+    }
+}
+
+impl TypeMapKey for Config {
+    type Value = Self;
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TweetRoute {
+    pub channel: u64,
+    #[serde(default)]
+    pub filter: TweetFilter,
+    /// Don't evaluate any routes after this one if it matches.
+    #[serde(default = "bool::default")]
+    pub stop: bool,
+}
+
+/// Every field that is set has to match for the filter to match.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TweetFilter {
+    pub talents: Option<Vec<String>>,
+    pub branches: Option<Vec<HoloBranch>>,
+    pub generations: Option<Vec<HoloGeneration>>,
+    pub kinds: Option<Vec<TweetKind>>,
+    pub languages: Option<Vec<String>>,
+    pub keywords: Option<Vec<String>>,
+    #[serde(default = "Vec::new")]
+    pub excluded_keywords: Vec<String>,
+    pub has_media: Option<bool>,
+}
+
+impl TweetFilter {
+    #[must_use]
+    pub fn matches(&self, tweet: &TweetProperties<'_>) -> bool {
+        let user = tweet.user;
+
+        if let Some(talents) = &self.talents {
+            if !talents.iter().any(|t| {
+                t.eq_ignore_ascii_case(&user.name) || t.eq_ignore_ascii_case(&user.display_name)
+            }) {
+                return false;
+            }
+        }
+
+        if let Some(branches) = &self.branches {
+            if !branches.contains(&user.branch) {
+                return false;
+            }
+        }
+
+        if let Some(generations) = &self.generations {
+            if !generations.contains(&user.generation) {
+                return false;
+            }
+        }
+
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(&tweet.kind) {
+                return false;
+            }
+        }
+
+        if let Some(languages) = &self.languages {
+            match tweet.lang {
+                Some(lang) if languages.iter().any(|l| l == lang) => (),
+                _ => return false,
+            }
+        }
+
+        let text = tweet.text.to_lowercase();
+
+        if let Some(keywords) = &self.keywords {
+            if !keywords.iter().any(|k| text.contains(&k.to_lowercase())) {
+                return false;
+            }
+        }
+
+        if self
+            .excluded_keywords
+            .iter()
+            .any(|k| text.contains(&k.to_lowercase()))
+        {
+            return false;
+        }
+
+        if let Some(has_media) = self.has_media {
+            if has_media != tweet.has_media {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum TweetKind {
+    Original,
+    Reply,
+    Quote,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TweetProperties<'a> {
+    pub user: &'a User,
+    pub kind: TweetKind,
+    pub lang: Option<&'a str>,
+    pub text: &'a str,
+    pub has_media: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Deserialize, EnumIter, EnumString, ToString)]
+pub enum TranslatorType {
+    Azure,
+    DeepL,
+    Libre,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TranslationConfig {
+    /// How many days cached translations are kept for.
+    #[serde(default = "TranslationConfig::default_cache_ttl_days")]
+    pub cache_ttl_days: i64,
+
+    /// Translators to try in order, keyed by source language.
+    #[serde(default = "TranslationConfig::default_chains")]
+    pub chains: HashMap<String, Vec<TranslatorType>>,
+
+    /// Translators to try for languages without a chain of their own.
+    #[serde(default = "TranslationConfig::default_chain")]
+    pub default_chain: Vec<TranslatorType>,
+
+    /// Consecutive failures before a translator is taken out of rotation.
+    #[serde(default = "TranslationConfig::default_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// How long an unhealthy translator is skipped for, in seconds.
+    #[serde(default = "TranslationConfig::default_cooldown")]
+    pub cooldown: u64,
+
+    /// Fraction of the monthly DeepL character limit after which DeepL is skipped.
+    #[serde(default = "TranslationConfig::default_deepl_quota_threshold")]
+    pub deepl_quota_threshold: f32,
+
+    /// Policy used for channels and guilds without one of their own.
+    #[serde(default)]
+    pub default_policy: TranslationPolicy,
+
+    /// Policies keyed by guild ID.
+    #[serde(default = "HashMap::new")]
+    pub guild_policies: HashMap<u64, TranslationPolicy>,
+
+    /// Policies keyed by channel ID, taking precedence over guild policies.
+    #[serde(default = "HashMap::new")]
+    pub channel_policies: HashMap<u64, TranslationPolicy>,
+}
+
+impl TranslationConfig {
+    const fn default_cache_ttl_days() -> i64 {
+        30
+    }
+
+    fn default_chains() -> HashMap<String, Vec<TranslatorType>> {
+        let chain = vec![
+            TranslatorType::Libre,
+            TranslatorType::DeepL,
+            TranslatorType::Azure,
+        ];
+
+        ["ja", "de"]
+            .iter()
+            .map(|lang| ((*lang).to_owned(), chain.clone()))
+            .collect()
+    }
+
+    fn default_chain() -> Vec<TranslatorType> {
+        vec![TranslatorType::Azure, TranslatorType::Libre]
+    }
+
+    const fn default_failure_threshold() -> u32 {
+        3
+    }
+
+    const fn default_cooldown() -> u64 {
+        5 * 60
+    }
+
+    const fn default_deepl_quota_threshold() -> f32 {
+        0.95
+    }
+
+    #[must_use]
+    pub fn chain_for_lang(&self, lang: &str) -> &[TranslatorType] {
+        self.chains
+            .get(normalise_lang(lang))
+            .unwrap_or(&self.default_chain)
+    }
+
+    #[must_use]
+    pub fn policy_for(&self, channel: ChannelId, guild: Option<GuildId>) -> &TranslationPolicy {
+        self.channel_policies
+            .get(channel.as_u64())
+            .or_else(|| guild.and_then(|g| self.guild_policies.get(g.as_u64())))
+            .unwrap_or(&self.default_policy)
+    }
+}
+
+impl Default for TranslationConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl_days: Self::default_cache_ttl_days(),
+            chains: Self::default_chains(),
+            default_chain: Self::default_chain(),
+            failure_threshold: Self::default_failure_threshold(),
+            cooldown: Self::default_cooldown(),
+            deepl_quota_threshold: Self::default_deepl_quota_threshold(),
+            default_policy: TranslationPolicy::default(),
+            guild_policies: HashMap::new(),
+            channel_policies: HashMap::new(),
+        }
+    }
+}
+
+/// Decides whether, and into which language, messages posted to a channel are translated.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TranslationPolicy {
+    #[serde(default = "TranslationPolicy::default_enabled")]
+    pub enabled: bool,
+
+    #[serde(default = "TranslationPolicy::default_target")]
+    pub target: String,
+
+    /// Languages that get translated, all others are posted as is.
+    #[serde(default = "TranslationPolicy::default_source_languages")]
+    pub source_languages: Vec<String>,
+}
+
+impl TranslationPolicy {
+    const fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_target() -> String {
+        "en".to_owned()
+    }
+
+    fn default_source_languages() -> Vec<String> {
+        vec!["id".to_owned(), "de".to_owned(), "ja".to_owned()]
+    }
+
+    #[must_use]
+    pub fn should_translate(&self, lang: &str) -> bool {
+        let lang = normalise_lang(lang);
+
+        self.enabled
+            && lang != normalise_lang(&self.target)
+            && self
+                .source_languages
+                .iter()
+                .any(|l| normalise_lang(l) == lang)
+    }
+}
+
+impl Default for TranslationPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            target: Self::default_target(),
+            source_languages: Self::default_source_languages(),
+        }
+    }
+}
+
+/// Maps the non-standard language codes used by Twitter to their ISO 639-1 codes.
+#[must_use]
+pub fn normalise_lang(lang: &str) -> &str {
+    match lang {
+        "jp" => "ja",
+        "in" => "id",
+        _ => lang,
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FanartConfig {
+    pub channel: u64,
+    /// Likes, retweets, replies and quotes a fanart tweet needs to be posted. Tweets have
+    /// no engagement when they come in, so they're checked again a while later.
+    #[serde(default)]
+    pub min_engagement: Option<u64>,
+    #[serde(default)]
+    pub sensitive_content: SensitiveContentPolicy,
+}
+
+/// Discord scheduled events created for upcoming streams.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScheduledEventsConfig {
+    pub guild: u64,
+    /// Only create events for talents from these branches. Empty means every branch.
+    #[serde(default = "HashSet::new")]
+    pub branches: HashSet<HoloBranch>,
+    /// Only create events for these talents, by name. Empty means every talent.
+    #[serde(default = "HashSet::new")]
+    pub talents: HashSet<String>,
+}
+
+impl ScheduledEventsConfig {
+    #[must_use]
+    pub fn includes(&self, user: &User) -> bool {
+        (self.branches.is_empty() || self.branches.contains(&user.branch))
+            && (self.talents.is_empty() || self.talents.contains(&user.name))
+    }
+}
+
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum SensitiveContentPolicy {
+    Filter,
+    Spoiler,
+}
+
+impl Default for SensitiveContentPolicy {
+    fn default() -> Self {
+        Self::Filter
+    }
+}
+
+/// How archived stream chats are posted to the log channel.
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum ChatLogExport {
+    /// Embeds of up to six 1000 character chunks each, linked from an index message.
+    Embeds,
+    /// Markdown and HTML files of the whole chat, attached to a summary embed.
+    Files,
+}
+
+impl Default for ChatLogExport {
+    fn default() -> Self {
+        Self::Embeds
+    }
+}
+
+pub trait SaveToDatabase {
+    fn save_to_database(&self, handle: &Connection) -> anyhow::Result<()>;
+}
+
+pub trait LoadFromDatabase {
+    type Item;
+
+    fn load_from_database(handle: &Connection) -> anyhow::Result<Vec<Self::Item>>
+    where
+        Self::Item: Sized;
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct User {
+    pub name: String,
+    pub display_name: String,
+    pub emoji: String,
+
+    pub branch: HoloBranch,
+    pub generation: HoloGeneration,
+
+    pub icon: Url,
+    pub channel: String,
+
+    pub birthday: (u32, u32),
+    #[serde(with = "super::serializers::timezone")]
+    pub timezone: chrono_tz::Tz,
+
+    pub twitter_handle: String,
+    pub twitter_id: u64,
+    pub schedule_keyword: Option<String>,
+    pub fanart_hashtag: Option<String>,
+    /// Other names the talent goes by, such as their name in Japanese.
+    #[serde(default = "Vec::new")]
+    pub aliases: Vec<String>,
+
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub colour: u32,
+    pub discord_role: u64,
+}
+
+impl User {
+    #[must_use]
+    pub fn get_next_birthday(&self) -> DateTime<Utc> {
+        let now = Utc::now();
+        let mut year = now.year();
+
+        let (day, month) = self.birthday;
+
+        if month < now.month() || (month == now.month() && day <= now.day()) {
+            year += 1;
+        }
+
+        self.timezone
+            .ymd(year, month, day)
+            .and_hms(12, 0, 0)
+            .with_timezone(&Utc)
+    }
+}
+
+impl std::fmt::Display for User {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+impl PartialEq for User {
+    fn eq(&self, other: &Self) -> bool {
+        self.twitter_id == other.twitter_id
+    }
+}
+
+pub trait UserCollection {
+    fn find_by_name(&self, name: &str) -> Option<&User>;
+}
+
+impl UserCollection for &[User] {
+    fn find_by_name(&self, name: &str) -> Option<&User> {
+        self.iter().find(|u| {
+            u.display_name
+                .to_lowercase()
+                .contains(&name.trim().to_lowercase())
+        })
+    }
+}
+
+impl UserCollection for Vec<User> {
+    fn find_by_name(&self, name: &str) -> Option<&User> {
+        self.iter().find(|u| {
+            u.display_name
+                .to_lowercase()
+                .contains(&name.trim().to_lowercase())
+        })
+    }
+}
+
+impl LoadFromDatabase for User {
+    type Item = User;
+
+    fn load_from_database(handle: &Connection) -> anyhow::Result<Vec<Self::Item>> {
+        let mut stmt = handle.prepare("SELECT name, display_name, emoji, branch, generation, icon_url, channel_id, birthday_day, birthday_month, 
+                                                timezone, twitter_name, twitter_id, colour, discord_role, schedule_keyword, fanart_hashtag, aliases
+                                                FROM users").context(here!())?;
+
+        let users = stmt.query_and_then([], |row| -> anyhow::Result<User> {
+            let timezone =
+                chrono_tz::Tz::from_str(&row.get::<&str, String>("timezone").context(here!())?)
+                    .map_err(|e| anyhow!(e))
+                    .context(here!())?;
+            let colour =
+                u32::from_str_radix(&row.get::<&str, String>("colour").context(here!())?, 16)
+                    .context(here!())?;
+
+            Ok(User {
+                name: row.get("name").context(here!())?,
+                display_name: row.get("display_name").context(here!())?,
+                emoji: row.get("emoji").context(here!())?,
+                branch: row.get("branch").context(here!())?,
+                generation: row.get("generation").context(here!())?,
+                icon: row.get("icon_url").context(here!())?,
+                channel: row.get("channel_id").context(here!())?,
+                birthday: (
+                    row.get("birthday_day").context(here!())?,
+                    row.get("birthday_month").context(here!())?,
+                ),
+                timezone,
+                twitter_handle: row.get("twitter_name").context(here!())?,
+                twitter_id: row.get("twitter_id").context(here!())?,
+                colour,
+                discord_role: row.get("discord_role").context(here!())?,
+                schedule_keyword: row.get("schedule_keyword").context(here!())?,
+                fanart_hashtag: row.get("fanart_hashtag").context(here!())?,
+                aliases: row
+                    .get::<_, Option<String>>("aliases")
+                    .context(here!())?
+                    .map(|a| {
+                        a.split(',')
+                            .map(str::trim)
+                            .filter(|a| !a.is_empty())
+                            .map(str::to_owned)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+        })?;
+
+        users.collect::<anyhow::Result<Vec<_>>>()
+    }
+}
+
+/// A curated term and the English form it should always be translated to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GlossaryTerm {
+    pub term: String,
+    pub translation: String,
+}
+
+impl LoadFromDatabase for GlossaryTerm {
+    type Item = GlossaryTerm;
+
+    fn load_from_database(handle: &Connection) -> anyhow::Result<Vec<Self::Item>> {
+        let mut stmt = handle
+            .prepare("SELECT term, translation FROM GlossaryTerms")
+            .context(here!())?;
+
+        let terms = stmt
+            .query_map([], |row| {
+                Ok(GlossaryTerm {
+                    term: row.get("term")?,
+                    translation: row.get("translation")?,
+                })
+            })
+            .context(here!())?
+            .collect::<Result<Vec<_>, _>>()
+            .context(here!())?;
+
+        Ok(terms)
+    }
+}
+
+/// Notification post whose text can be customised per guild.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, EnumIter, EnumString, ToString)]
+#[strum(serialize_all = "snake_case")]
+pub enum TemplateKind {
+    Live,
+    LiveEnded,
+    ScheduleUpdate,
+    Birthday,
+    Reminder,
+    Tweet,
+}
+
+/// Text of a notification post. Placeholders such as `{talent}` are filled in when posting,
+/// see [`EmbedTemplate::PLACEHOLDERS`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmbedTemplate {
+    pub content: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub footer: Option<String>,
+}
+
+impl EmbedTemplate {
+    pub const PLACEHOLDERS: &'static [(&'static str, &'static str)] = &[
+        ("talent", "Display name of the talent."),
+        (
+            "mention",
+            "Mention of the talent's role, or of the subscribers of a reminder.",
+        ),
+        ("title", "Title of the stream."),
+        ("text", "Text of the tweet, schedule update or reminder."),
+        ("url", "Link to the stream or tweet."),
+        (
+            "time",
+            "Start of the stream, or time of the post, in the reader's timezone.",
+        ),
+        (
+            "relative_time",
+            "Same as `time`, but relative, like \"in 5 minutes\".",
+        ),
+    ];
+
+    /// Discord's limits on message contents and embed parts, in characters.
+    pub const CONTENT_LIMIT: usize = 2000;
+    pub const TITLE_LIMIT: usize = 256;
+    pub const DESCRIPTION_LIMIT: usize = 4096;
+    pub const FOOTER_LIMIT: usize = 2048;
+
+    #[must_use]
+    pub fn default_for(kind: TemplateKind) -> Self {
+        let (content, title, description) = match kind {
+            TemplateKind::Live => (
+                Some("{mention}"),
+                Some("{talent} just went live!"),
+                Some("{title}"),
+            ),
+            TemplateKind::LiveEnded => (None, Some("{talent} was live"), Some("{title}")),
+            TemplateKind::ScheduleUpdate => (
+                Some("{mention}"),
+                Some("{talent} just released a schedule update!"),
+                Some("{text}"),
+            ),
+            TemplateKind::Birthday => (
+                Some("{mention}"),
+                Some("It is {talent}'s birthday today!!!"),
+                None,
+            ),
+            TemplateKind::Reminder => (Some("{mention}"), Some("Reminder!"), Some("{text}")),
+            TemplateKind::Tweet => (None, None, Some("{text}")),
+        };
+
+        Self {
+            content: content.map(str::to_owned),
+            title: title.map(str::to_owned),
+            description: description.map(str::to_owned),
+            footer: None,
+        }
+    }
+
+    /// Loads the guild's template, falling back to the default one.
+    pub fn load(handle: &Connection, guild: GuildId, kind: TemplateKind) -> anyhow::Result<Self> {
+        let template = handle
+            .query_row(
+                "SELECT content, title, description, footer FROM EmbedTemplates WHERE guild_id = ? AND kind = ?",
+                params![guild.as_u64(), kind.to_string()],
+                |row| {
+                    Ok(Self {
+                        content: row.get("content")?,
+                        title: row.get("title")?,
+                        description: row.get("description")?,
+                        footer: row.get("footer")?,
+                    })
+                },
+            )
+            .optional()
+            .context(here!())?;
+
+        Ok(template.unwrap_or_else(|| Self::default_for(kind)))
+    }
+
+    pub fn save(
+        &self,
+        handle: &Connection,
+        guild: GuildId,
+        kind: TemplateKind,
+    ) -> anyhow::Result<()> {
+        handle
+            .execute(
+                "INSERT OR REPLACE INTO EmbedTemplates (guild_id, kind, content, title, description, footer) VALUES (?, ?, ?, ?, ?, ?)",
+                params![
+                    guild.as_u64(),
+                    kind.to_string(),
+                    self.content,
+                    self.title,
+                    self.description,
+                    self.footer
+                ],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Removes the guild's template, so the default one is used again.
+    pub fn reset(handle: &Connection, guild: GuildId, kind: TemplateKind) -> anyhow::Result<()> {
+        handle
+            .execute(
+                "DELETE FROM EmbedTemplates WHERE guild_id = ? AND kind = ?",
+                params![guild.as_u64(), kind.to_string()],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Fills in the placeholders. Unknown placeholders are left as they are,
+    /// parts that end up empty are left out, and parts longer than Discord allows are cut off.
+    #[must_use]
+    pub fn render(&self, values: &HashMap<&str, String>) -> Self {
+        let filled = self.fill(values);
+
+        let truncate = |text: Option<String>, limit: usize| {
+            text.map(|t| match t.char_indices().nth(limit) {
+                Some((end, _)) => t[..end].to_owned(),
+                None => t,
+            })
+        };
+
+        Self {
+            content: truncate(filled.content, Self::CONTENT_LIMIT),
+            title: truncate(filled.title, Self::TITLE_LIMIT),
+            description: truncate(filled.description, Self::DESCRIPTION_LIMIT),
+            footer: truncate(filled.footer, Self::FOOTER_LIMIT),
+        }
+    }
+
+    /// Returns the name and limit of the first part that would be longer than Discord allows
+    /// once the placeholders are filled in.
+    #[must_use]
+    pub fn overlong_part(&self, values: &HashMap<&str, String>) -> Option<(&'static str, usize)> {
+        let filled = self.fill(values);
+
+        [
+            ("content", &filled.content, Self::CONTENT_LIMIT),
+            ("title", &filled.title, Self::TITLE_LIMIT),
+            ("description", &filled.description, Self::DESCRIPTION_LIMIT),
+            ("footer", &filled.footer, Self::FOOTER_LIMIT),
+        ]
+        .iter()
+        .find(|(_, text, limit)| text.as_ref().map_or(false, |t| t.chars().count() > *limit))
+        .map(|(name, _, limit)| (*name, *limit))
+    }
+
+    fn fill(&self, values: &HashMap<&str, String>) -> Self {
+        let placeholder_rgx: &'static Regex = regex!(r"\{(\w+)\}");
+
+        let fill = |text: &Option<String>| {
+            text.as_ref()
+                .map(|t| {
+                    placeholder_rgx
+                        .replace_all(t, |caps: &regex::Captures| {
+                            values
+                                .get(&caps[1])
+                                .cloned()
+                                .unwrap_or_else(|| caps[0].to_owned())
+                        })
+                        .trim()
+                        .to_owned()
+                })
+                .filter(|t| !t.is_empty())
+        };
+
+        Self {
+            content: fill(&self.content),
+            title: fill(&self.title),
+            description: fill(&self.description),
+            footer: fill(&self.footer),
+        }
+    }
+
+    /// Sets the title, description and footer of the embed.
+    pub fn apply<'a>(&self, embed: &'a mut CreateEmbed) -> &'a mut CreateEmbed {
+        if let Some(title) = &self.title {
+            embed.title(title);
+        }
+
+        if let Some(description) = &self.description {
+            embed.description(description);
+        }
+
+        if let Some(footer) = &self.footer {
+            embed.footer(|f| f.text(footer));
+        }
+
+        embed
+    }
+}
+
+/// Kind of post members can get DMs about.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, EnumIter, EnumString, ToString)]
+#[strum(serialize_all = "snake_case")]
+pub enum SubscriptionEvent {
+    Live,
+    Schedule,
+    Tweet,
+    Birthday,
+}
+
+/// A member getting DMs about a talent's posts. The talent is identified by [`User::name`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub user: UserId,
+    pub talent: String,
+    pub event: SubscriptionEvent,
+}
+
+impl Subscription {
+    pub fn subscribe(
+        handle: &Connection,
+        user: UserId,
+        talent: &str,
+        events: &[SubscriptionEvent],
+    ) -> anyhow::Result<()> {
+        let mut stmt = handle
+            .prepare_cached(
+                "INSERT OR IGNORE INTO Subscriptions (user_id, talent, event, subscribed_at) VALUES (?, ?, ?, ?)",
+            )
+            .context(here!())?;
+
+        let tx = handle.unchecked_transaction().context(here!())?;
+
+        for event in events {
+            stmt.execute(params![
+                user.as_u64(),
+                talent,
+                event.to_string(),
+                Utc::now()
+            ])
+            .context(here!())?;
+        }
+
+        tx.commit().context(here!())?;
+        Ok(())
+    }
+
+    /// Removes the subscriptions to the talent, or only the ones for `event` if set.
+    /// Returns the number of removed subscriptions.
+    pub fn unsubscribe(
+        handle: &Connection,
+        user: UserId,
+        talent: &str,
+        event: Option<SubscriptionEvent>,
+    ) -> anyhow::Result<usize> {
+        let removed = match event {
+            Some(event) => handle.execute(
+                "DELETE FROM Subscriptions WHERE user_id = ? AND talent = ? AND event = ?",
+                params![user.as_u64(), talent, event.to_string()],
+            ),
+            None => handle.execute(
+                "DELETE FROM Subscriptions WHERE user_id = ? AND talent = ?",
+                params![user.as_u64(), talent],
+            ),
+        }
+        .context(here!())?;
+
+        Ok(removed)
+    }
+
+    pub fn for_user(handle: &Connection, user: UserId) -> anyhow::Result<Vec<Self>> {
+        let mut stmt = handle
+            .prepare(
+                "SELECT talent, event FROM Subscriptions WHERE user_id = ? ORDER BY talent, event",
+            )
+            .context(here!())?;
+
+        let subscriptions = stmt
+            .query_and_then([user.as_u64()], |row| -> anyhow::Result<Self> {
+                Ok(Self {
+                    user,
+                    talent: row.get("talent")?,
+                    event: SubscriptionEvent::from_str(&row.get::<_, String>("event")?)
+                        .context(here!())?,
+                })
+            })
+            .context(here!())?
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(subscriptions)
+    }
+
+    pub fn subscribers(
+        handle: &Connection,
+        talent: &str,
+        event: SubscriptionEvent,
+    ) -> anyhow::Result<Vec<UserId>> {
+        let mut stmt = handle
+            .prepare_cached("SELECT user_id FROM Subscriptions WHERE talent = ? AND event = ?")
+            .context(here!())?;
 
-        let db_handle = Connection::open(&config.database_path).context(here!())?;
+        let subscribers = stmt
+            .query_map(params![talent, event.to_string()], |row| {
+                row.get::<_, u64>(0).map(UserId)
+            })
+            .context(here!())?
+            .collect::<Result<Vec<_>, _>>()
+            .context(here!())?;
 
-        Self::initialize_tables(&db_handle)?;
+        Ok(subscribers)
+    }
+}
 
-        config.users = User::load_from_database(&db_handle)?;
+/// Messages making up a role menu, along with the roster they were generated from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoleMenu {
+    pub channel: ChannelId,
+    pub guild: GuildId,
+    pub messages: Vec<MessageId>,
+    pub roster: String,
+}
 
-        Ok(config)
+impl RoleMenu {
+    /// Summary of the parts of the roster shown in role menus, used to tell when they're outdated.
+    #[must_use]
+    pub fn roster_signature(users: &[User]) -> String {
+        users
+            .iter()
+            .map(|u| {
+                format!(
+                    "{}:{}:{}:{}:{}",
+                    u.discord_role,
+                    u.display_name,
+                    u.emoji,
+                    u.branch.to_string(),
+                    u.generation.to_string()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(";")
     }
 
-    fn initialize_tables(handle: &Connection) -> anyhow::Result<()> {
-        handle.execute("CREATE TABLE IF NOT EXISTS emoji_usage (emoji_id INTEGER PRIMARY KEY, text_count INTEGER NOT NULL, reaction_count INTEGER NOT NULL)", []).context(here!())?;
-        handle
-            .execute(
-                "CREATE TABLE IF NOT EXISTS Quotes (quote BLOB NOT NULL)",
-                [],
-            )
-            .context(here!())?;
+    pub fn save(&self, handle: &Connection) -> anyhow::Result<()> {
         handle
             .execute(
-                "CREATE TABLE IF NOT EXISTS Reminders (reminder BLOB NOT NULL)",
-                [],
+                "INSERT OR REPLACE INTO RoleMenus (channel_id, guild_id, message_ids, roster) VALUES (?, ?, ?, ?)",
+                params![
+                    self.channel.as_u64(),
+                    self.guild.as_u64(),
+                    self.messages
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    self.roster
+                ],
             )
             .context(here!())?;
 
         Ok(())
     }
+}
 
-    pub fn get_database_handle(&self) -> anyhow::Result<Connection> {
-        Connection::open(&self.database_path).context(here!())
+impl LoadFromDatabase for RoleMenu {
+    type Item = RoleMenu;
+
+    fn load_from_database(handle: &Connection) -> anyhow::Result<Vec<Self::Item>> {
+        let mut stmt = handle
+            .prepare("SELECT channel_id, guild_id, message_ids, roster FROM RoleMenus")
+            .context(here!())?;
+
+        let menus = stmt
+            .query_and_then([], |row| -> anyhow::Result<RoleMenu> {
+                Ok(RoleMenu {
+                    channel: ChannelId(row.get("channel_id").context(here!())?),
+                    guild: GuildId(row.get("guild_id").context(here!())?),
+                    messages: row
+                        .get::<_, String>("message_ids")
+                        .context(here!())?
+                        .split(',')
+                        .filter_map(|id| id.parse().ok())
+                        .map(MessageId)
+                        .collect(),
+                    roster: row.get("roster").context(here!())?,
+                })
+            })
+            .context(here!())?;
+
+        menus.collect()
     }
 }
 
-impl TypeMapKey for Config {
-    type Value = Self;
+/// Message from an archived stream chat.
+#[derive(Debug, Clone)]
+pub struct ArchivedMessage {
+    pub author: UserId,
+    pub author_name: String,
+    pub content: String,
+    /// Time since the stream started.
+    pub timestamp: chrono::Duration,
+    pub attachment_urls: Vec<String>,
+    pub video_id: Option<String>,
 }
 
-pub trait SaveToDatabase {
-    fn save_to_database(&self, handle: &Connection) -> anyhow::Result<()>;
+impl ArchivedMessage {
+    #[must_use]
+    pub fn format_timestamp(&self) -> String {
+        let timestamp = self.plain_timestamp();
+
+        match self.vod_url() {
+            Some(url) => format!("[{time}]({url})", time = timestamp, url = url),
+            None => timestamp,
+        }
+    }
+
+    #[must_use]
+    pub fn plain_timestamp(&self) -> String {
+        let hours = (self.timestamp.num_hours() != 0)
+            .then(|| format!("{:02}:", self.timestamp.num_hours().abs()))
+            .unwrap_or_default();
+
+        let minutes = self.timestamp.num_minutes() % 60;
+        let seconds = self.timestamp.num_seconds() % 60;
+
+        // Check if message was sent before the stream started.
+        if self.timestamp.num_seconds() < 0 {
+            format!("-{}{:02}:{:02}", hours, minutes.abs(), seconds.abs())
+        } else {
+            format!("{}{:02}:{:02}", hours, minutes, seconds)
+        }
+    }
+
+    /// Link to the moment in the VOD the message was sent at, unless it was sent before the stream started.
+    #[must_use]
+    pub fn vod_url(&self) -> Option<String> {
+        if self.timestamp.num_seconds() < 0 {
+            return None;
+        }
+
+        self.video_id.as_ref().map(|id| {
+            format!(
+                "https://youtu.be/{id}?t={secs}",
+                id = id,
+                secs = self.timestamp.num_seconds()
+            )
+        })
+    }
+
+    fn from_row(row: &rusqlite::Row, video_id: Option<String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            author: UserId(row.get("author_id").context(here!())?),
+            author_name: row.get("author_name").context(here!())?,
+            content: row.get("content").context(here!())?,
+            timestamp: chrono::Duration::seconds(row.get("offset_seconds").context(here!())?),
+            attachment_urls: row
+                .get::<_, String>("attachments")
+                .context(here!())?
+                .split_whitespace()
+                .map(str::to_owned)
+                .collect(),
+            video_id,
+        })
+    }
 }
 
-pub trait LoadFromDatabase {
-    type Item;
+impl std::fmt::Display for ArchivedMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} {}: {}",
+            self.format_timestamp(),
+            Mention::from(self.author),
+            self.content
+        )?;
+
+        if !self.attachment_urls.is_empty() {
+            writeln!(f, "{}", self.attachment_urls.join(" "))
+        } else {
+            Ok(())
+        }
+    }
+}
 
-    fn load_from_database(handle: &Connection) -> anyhow::Result<Vec<Self::Item>>
-    where
-        Self::Item: Sized;
+/// Stream chat kept in the database after its channel was archived, so it can be searched.
+#[derive(Debug, Clone)]
+pub struct ChatArchive {
+    /// Video ID of the stream, or the ID of the chat channel if the stream wasn't known.
+    pub id: String,
+    pub video_id: Option<String>,
+    pub title: String,
+    /// Name of the talent who streamed.
+    pub talent: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub archived_at: DateTime<Utc>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
-pub struct User {
-    pub name: String,
-    pub display_name: String,
-    pub emoji: String,
+impl ChatArchive {
+    /// Saves the archive along with its messages, replacing any earlier archive of the stream.
+    pub fn save(&self, handle: &Connection, messages: &[ArchivedMessage]) -> anyhow::Result<()> {
+        let transaction = handle.unchecked_transaction().context(here!())?;
 
-    pub branch: HoloBranch,
-    pub generation: HoloGeneration,
+        transaction
+            .execute(
+                "INSERT OR REPLACE INTO ChatArchives (id, video_id, title, talent, started_at, archived_at) VALUES (?, ?, ?, ?, ?, ?)",
+                params![self.id, self.video_id, self.title, self.talent, self.started_at, self.archived_at],
+            )
+            .context(here!())?;
 
-    pub icon: Url,
-    pub channel: String,
+        transaction
+            .execute("DELETE FROM ChatMessages WHERE archive_id = ?", [&self.id])
+            .context(here!())?;
 
-    pub birthday: (u32, u32),
-    #[serde(with = "super::serializers::timezone")]
-    pub timezone: chrono_tz::Tz,
+        {
+            let mut stmt = transaction
+                .prepare("INSERT INTO ChatMessages (archive_id, author_id, author_name, content, offset_seconds, attachments) VALUES (?, ?, ?, ?, ?, ?)")
+                .context(here!())?;
+
+            for message in messages {
+                stmt.execute(params![
+                    self.id,
+                    message.author.as_u64(),
+                    message.author_name,
+                    message.content,
+                    message.timestamp.num_seconds(),
+                    message.attachment_urls.join(" ")
+                ])
+                .context(here!())?;
+            }
+        }
 
-    pub twitter_handle: String,
-    pub twitter_id: u64,
-    pub schedule_keyword: Option<String>,
+        transaction.commit().context(here!())?;
 
-    #[serde(with = "SerHex::<StrictPfx>")]
-    pub colour: u32,
-    pub discord_role: u64,
-}
+        Ok(())
+    }
 
-impl User {
-    #[must_use]
-    pub fn get_next_birthday(&self) -> DateTime<Utc> {
-        let now = Utc::now();
-        let mut year = now.year();
+    pub fn load(handle: &Connection, id: &str) -> anyhow::Result<Option<Self>> {
+        handle
+            .query_row(
+                "SELECT id, video_id, title, talent, started_at, archived_at FROM ChatArchives WHERE id = ?",
+                [id],
+                Self::from_row,
+            )
+            .optional()
+            .context(here!())
+    }
 
-        let (day, month) = self.birthday;
+    /// Returns the archived messages in the order they were sent.
+    pub fn messages(&self, handle: &Connection) -> anyhow::Result<Vec<ArchivedMessage>> {
+        let mut stmt = handle
+            .prepare("SELECT author_id, author_name, content, offset_seconds, attachments FROM ChatMessages WHERE archive_id = ? ORDER BY id")
+            .context(here!())?;
 
-        if month < now.month() || (month == now.month() && day <= now.day()) {
-            year += 1;
-        }
+        let messages = stmt
+            .query_and_then([&self.id], |row| {
+                ArchivedMessage::from_row(row, self.video_id.clone())
+            })
+            .context(here!())?;
 
-        self.timezone
-            .ymd(year, month, day)
-            .and_hms(12, 0, 0)
-            .with_timezone(&Utc)
+        messages.collect()
     }
 
-    #[must_use]
-    pub fn get_twitter_channel(&self, config: &Config) -> ChannelId {
-        ChannelId(
-            *config
-                .twitter_feeds
-                .get(&self.branch)
-                .unwrap()
-                .get(&self.generation)
-                .unwrap(),
-        )
+    /// Whether archived chats can be searched, which depends on SQLite supporting FTS5.
+    pub fn search_available(handle: &Connection) -> anyhow::Result<bool> {
+        handle
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'ChatMessagesSearch')",
+                [],
+                |row| row.get(0),
+            )
+            .context(here!())
     }
-}
 
-impl std::fmt::Display for User {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.display_name)
+    /// Finds the messages containing all the words in the query, best matches first.
+    pub fn search(
+        handle: &Connection,
+        query: &str,
+        talent: Option<&str>,
+        archive: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(Self, ArchivedMessage)>> {
+        // Quote every word, so the query isn't read as FTS syntax.
+        let query = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut stmt = handle
+            .prepare(
+                "SELECT a.id, a.video_id, a.title, a.talent, a.started_at, a.archived_at, m.author_id, m.author_name, m.content, m.offset_seconds, m.attachments
+                FROM ChatMessagesSearch s
+                JOIN ChatMessages m ON m.id = s.rowid
+                JOIN ChatArchives a ON a.id = m.archive_id
+                WHERE ChatMessagesSearch MATCH ?1 AND (?2 IS NULL OR a.talent = ?2) AND (?3 IS NULL OR a.id = ?3)
+                ORDER BY s.rank
+                LIMIT ?4",
+            )
+            .context(here!())?;
+
+        let matches = stmt
+            .query_and_then(
+                params![query, talent, archive, limit as i64],
+                |row| -> anyhow::Result<(Self, ArchivedMessage)> {
+                    let archive = Self::from_row(row).context(here!())?;
+                    let message = ArchivedMessage::from_row(row, archive.video_id.clone())?;
+
+                    Ok((archive, message))
+                },
+            )
+            .context(here!())?;
+
+        matches.collect()
     }
-}
 
-impl PartialEq for User {
-    fn eq(&self, other: &Self) -> bool {
-        self.twitter_id == other.twitter_id
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            video_id: row.get("video_id")?,
+            title: row.get("title")?,
+            talent: row.get("talent")?,
+            started_at: row.get("started_at")?,
+            archived_at: row.get("archived_at")?,
+        })
     }
 }
 
-pub trait UserCollection {
-    fn find_by_name(&self, name: &str) -> Option<&User>;
+/// Daily post listing the streams scheduled in the 24 hours after it's posted.
+#[derive(Debug, Clone)]
+pub struct ScheduleDigest {
+    pub guild: GuildId,
+    pub channel: ChannelId,
+    /// Local time of day the digest is posted at.
+    pub post_time: NaiveTime,
+    pub timezone: chrono_tz::Tz,
+    /// Only include talents from this branch.
+    pub branch: Option<HoloBranch>,
+    /// The latest digest, kept up to date until the next one is posted.
+    pub message: Option<MessageId>,
+    /// When the latest digest was due.
+    pub posted_for: Option<DateTime<Utc>>,
 }
 
-impl UserCollection for &[User] {
-    fn find_by_name(&self, name: &str) -> Option<&User> {
-        self.iter().find(|u| {
-            u.display_name
-                .to_lowercase()
-                .contains(&name.trim().to_lowercase())
-        })
+impl ScheduleDigest {
+    /// The most recent time the digest was due to be posted.
+    #[must_use]
+    pub fn last_due(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let local = now.with_timezone(&self.timezone);
+
+        // Local times skipped by daylight saving time fall back to an hour later.
+        let at_time = |date: Date<chrono_tz::Tz>| {
+            date.and_time(self.post_time)
+                .or_else(|| date.and_time(self.post_time + chrono::Duration::hours(1)))
+                .map(|t| t.with_timezone(&Utc))
+        };
+
+        match at_time(local.date()) {
+            Some(due) if due <= now => due,
+            Some(due) => at_time(local.date().pred()).unwrap_or(due - chrono::Duration::days(1)),
+            None => now,
+        }
     }
-}
 
-impl UserCollection for Vec<User> {
-    fn find_by_name(&self, name: &str) -> Option<&User> {
-        self.iter().find(|u| {
-            u.display_name
-                .to_lowercase()
-                .contains(&name.trim().to_lowercase())
-        })
+    pub fn save(&self, handle: &Connection) -> anyhow::Result<()> {
+        handle
+            .execute(
+                "INSERT OR REPLACE INTO ScheduleDigests (guild_id, channel_id, post_time, timezone, branch, message_id, posted_for) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    self.guild.as_u64(),
+                    self.channel.as_u64(),
+                    self.post_time,
+                    self.timezone.name(),
+                    self.branch.map(|b| b.to_string()),
+                    self.message.map(|m| *m.as_u64()),
+                    self.posted_for
+                ],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Saves which digest was posted last, unless the digest has been changed or removed since.
+    pub fn save_progress(&self, handle: &Connection) -> anyhow::Result<()> {
+        handle
+            .execute(
+                "UPDATE ScheduleDigests SET message_id = ?, posted_for = ? WHERE guild_id = ? AND channel_id = ? AND post_time = ? AND timezone = ?",
+                params![
+                    self.message.map(|m| *m.as_u64()),
+                    self.posted_for,
+                    self.guild.as_u64(),
+                    self.channel.as_u64(),
+                    self.post_time,
+                    self.timezone.name()
+                ],
+            )
+            .context(here!())?;
+
+        Ok(())
+    }
+
+    /// Stops posting the guild's digest. Returns whether there was one.
+    pub fn remove(handle: &Connection, guild: GuildId) -> anyhow::Result<bool> {
+        let removed = handle
+            .execute(
+                "DELETE FROM ScheduleDigests WHERE guild_id = ?",
+                [guild.as_u64()],
+            )
+            .context(here!())?;
+
+        Ok(removed > 0)
     }
 }
 
-impl LoadFromDatabase for User {
-    type Item = User;
+impl LoadFromDatabase for ScheduleDigest {
+    type Item = ScheduleDigest;
 
     fn load_from_database(handle: &Connection) -> anyhow::Result<Vec<Self::Item>> {
-        let mut stmt = handle.prepare("SELECT name, display_name, emoji, branch, generation, icon_url, channel_id, birthday_day, birthday_month, 
-                                                timezone, twitter_name, twitter_id, colour, discord_role, schedule_keyword
-                                                FROM users").context(here!())?;
-
-        let users = stmt.query_and_then([], |row| -> anyhow::Result<User> {
-            let timezone =
-                chrono_tz::Tz::from_str(&row.get::<&str, String>("timezone").context(here!())?)
-                    .map_err(|e| anyhow!(e))
-                    .context(here!())?;
-            let colour =
-                u32::from_str_radix(&row.get::<&str, String>("colour").context(here!())?, 16)
-                    .context(here!())?;
+        let mut stmt = handle
+            .prepare("SELECT guild_id, channel_id, post_time, timezone, branch, message_id, posted_for FROM ScheduleDigests")
+            .context(here!())?;
 
-            Ok(User {
-                name: row.get("name").context(here!())?,
-                display_name: row.get("display_name").context(here!())?,
-                emoji: row.get("emoji").context(here!())?,
-                branch: row.get("branch").context(here!())?,
-                generation: row.get("generation").context(here!())?,
-                icon: row.get("icon_url").context(here!())?,
-                channel: row.get("channel_id").context(here!())?,
-                birthday: (
-                    row.get("birthday_day").context(here!())?,
-                    row.get("birthday_month").context(here!())?,
-                ),
-                timezone,
-                twitter_handle: row.get("twitter_name").context(here!())?,
-                twitter_id: row.get("twitter_id").context(here!())?,
-                colour,
-                discord_role: row.get("discord_role").context(here!())?,
-                schedule_keyword: row.get("schedule_keyword").context(here!())?,
+        let digests = stmt
+            .query_and_then([], |row| -> anyhow::Result<ScheduleDigest> {
+                Ok(ScheduleDigest {
+                    guild: GuildId(row.get("guild_id").context(here!())?),
+                    channel: ChannelId(row.get("channel_id").context(here!())?),
+                    post_time: row.get("post_time").context(here!())?,
+                    timezone: row
+                        .get::<_, String>("timezone")
+                        .context(here!())?
+                        .parse()
+                        .map_err(|e: String| anyhow!(e))
+                        .context(here!())?,
+                    branch: row.get("branch").context(here!())?,
+                    message: row
+                        .get::<_, Option<u64>>("message_id")
+                        .context(here!())?
+                        .map(MessageId),
+                    posted_for: row.get("posted_for").context(here!())?,
+                })
             })
-        })?;
+            .context(here!())?;
 
-        users.collect::<anyhow::Result<Vec<_>>>()
+        digests.collect()
     }
 }
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Deserialize, Debug, Hash, Eq, PartialEq, Copy, Clone, EnumString, ToString, EnumIter)]
+#[derive(
+    Deserialize,
+    Debug,
+    Hash,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Copy,
+    Clone,
+    EnumString,
+    ToString,
+    EnumIter,
+)]
 #[non_exhaustive]
 pub enum HoloBranch {
     HoloJP,
@@ -297,7 +1692,9 @@ impl FromSql for HoloBranch {
 }
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Deserialize, Debug, Hash, Eq, PartialEq, Copy, Clone, EnumString, ToString)]
+#[derive(
+    Deserialize, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, EnumString, ToString,
+)]
 #[non_exhaustive]
 pub enum HoloGeneration {
     #[serde(rename = "0th")]
@@ -569,3 +1966,76 @@ pub enum EntryEvent<K, V> {
     Updated { key: K, value: V },
     Removed { key: K },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(title: &str, description: &str) -> EmbedTemplate {
+        EmbedTemplate {
+            content: Some("{mention}".to_owned()),
+            title: Some(title.to_owned()),
+            description: Some(description.to_owned()),
+            footer: None,
+        }
+    }
+
+    #[test]
+    fn render_fills_in_placeholders() {
+        let mut values = HashMap::new();
+        values.insert("talent", "Pekora".to_owned());
+        values.insert("title", "Minecraft".to_owned());
+
+        let rendered = template("{talent} just went live!", "{title} {unknown}").render(&values);
+
+        assert_eq!(rendered.content.as_deref(), Some("{mention}"));
+        assert_eq!(rendered.title.as_deref(), Some("Pekora just went live!"));
+        assert_eq!(rendered.description.as_deref(), Some("Minecraft {unknown}"));
+        assert_eq!(rendered.footer, None);
+    }
+
+    #[test]
+    fn render_leaves_out_empty_parts() {
+        let mut values = HashMap::new();
+        values.insert("text", "  ".to_owned());
+
+        let rendered = template("", "{text}").render(&values);
+
+        assert_eq!(rendered.title, None);
+        assert_eq!(rendered.description, None);
+    }
+
+    #[test]
+    fn render_truncates_to_discord_limits() {
+        let mut values = HashMap::new();
+        values.insert("title", "ぺ".repeat(300));
+        values.insert("text", "a".repeat(5000));
+
+        let rendered = template("{title}", "{text}").render(&values);
+
+        assert_eq!(
+            rendered.title.map(|t| t.chars().count()),
+            Some(EmbedTemplate::TITLE_LIMIT)
+        );
+        assert_eq!(
+            rendered.description.map(|d| d.chars().count()),
+            Some(EmbedTemplate::DESCRIPTION_LIMIT)
+        );
+    }
+
+    #[test]
+    fn overlong_part_checks_filled_in_text() {
+        let mut values = HashMap::new();
+        values.insert("title", "a".repeat(250));
+
+        assert_eq!(template("{title}", "").overlong_part(&values), None);
+        assert_eq!(
+            template("{title} is live!", "").overlong_part(&values),
+            Some(("title", EmbedTemplate::TITLE_LIMIT))
+        );
+        assert_eq!(
+            template("", &"a".repeat(4097)).overlong_part(&values),
+            Some(("description", EmbedTemplate::DESCRIPTION_LIMIT))
+        );
+    }
+}
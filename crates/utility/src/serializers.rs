@@ -37,6 +37,33 @@ pub mod utc_datetime {
     }
 }
 
+#[allow(dead_code)]
+pub mod opt_utc_datetime {
+    use chrono::{DateTime, Utc};
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(date: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match date {
+            Some(date) => super::utc_datetime::serialize(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super::utc_datetime")] DateTime<Utc>);
+
+        let wrapper = Option::<Wrapper>::deserialize(deserializer)?;
+        Ok(wrapper.map(|Wrapper(date)| date))
+    }
+}
+
 #[allow(dead_code)]
 pub mod string_to_number {
     use std::{fmt::Display, str::FromStr};